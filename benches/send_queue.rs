@@ -0,0 +1,57 @@
+//! Measures how long a burst of exports holds up the caller against a slow Parseable,
+//! sending directly versus through the send queue.
+//!
+//! Run with `cargo bench --bench send_queue`.
+
+use std::time::{Duration, Instant};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+const BURST: usize = 50;
+const RESPONSE_DELAY: Duration = Duration::from_millis(20);
+
+async fn measure(server: &MockServer, send_queue: Option<usize>) {
+    server.reset().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(RESPONSE_DELAY))
+        .mount(server)
+        .await;
+    let mut builder = support::builder_for(server);
+    if let Some(capacity) = send_queue {
+        builder = builder.with_send_queue(capacity);
+    }
+    let mut exporter = builder.build_exporter().expect("exporter builds");
+
+    let started = Instant::now();
+    // Exports are awaited one after the other, like the batch processor does.
+    for _ in 0..BURST {
+        exporter
+            .export(vec![support::span_data("burst")])
+            .await
+            .expect("export succeeds");
+    }
+    let blocked = started.elapsed();
+    while server.received_requests().await.unwrap_or_default().len() < BURST {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    let delivered = started.elapsed();
+
+    println!(
+        "send_queue={:<4} batches={BURST} caller_blocked={blocked:?} all_delivered={delivered:?}",
+        send_queue.map_or("off".to_string(), |capacity| capacity.to_string()),
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let server = MockServer::start().await;
+    measure(&server, None).await;
+    measure(&server, Some(16)).await;
+    measure(&server, Some(BURST)).await;
+}
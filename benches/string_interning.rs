@@ -0,0 +1,85 @@
+//! Counts the allocations made while flattening and serializing a batch whose spans
+//! share the same resource and attribute values, with and without string interning.
+//!
+//! Run with `cargo bench --bench string_interning`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use opentelemetry::sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry::sdk::Resource;
+use opentelemetry::KeyValue;
+use opentelemetry_parseable::ParseableExporterBuilder;
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const BATCH_SIZE: usize = 8192;
+
+fn batch() -> Vec<SpanData> {
+    let resource = Resource::new(
+        (0..10).map(|i| KeyValue::new(format!("resource.key{i}"), format!("resource-value-{i}"))),
+    );
+    (0..BATCH_SIZE)
+        .map(|_| {
+            let mut span = support::span_data("request");
+            span.resource = std::borrow::Cow::Owned(resource.clone());
+            span.attributes.insert(KeyValue::new("http.method", "GET"));
+            span.attributes.insert(KeyValue::new("http.route", "/api"));
+            span
+        })
+        .collect()
+}
+
+fn measure(interning: bool) {
+    let mut builder = ParseableExporterBuilder::default()
+        .with_host("127.0.0.1")
+        .with_port("9");
+    if interning {
+        builder = builder.with_string_interning();
+    }
+    let mut exporter = builder.build_exporter().expect("exporter builds");
+    let batch = batch();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    // The batch is flattened and serialized before the export future is returned; the
+    // request itself is never sent.
+    let export = exporter.export(batch);
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    drop(export);
+
+    println!(
+        "interning={interning:<5} spans={BATCH_SIZE} allocations={allocations:>8} time={elapsed:?}"
+    );
+}
+
+fn main() {
+    measure(false);
+    measure(true);
+}
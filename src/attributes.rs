@@ -0,0 +1,598 @@
+use opentelemetry::{Array, Key, Value};
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::Write,
+    sync::Arc,
+};
+
+/// Normalization applied to string attribute values before they are serialized.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOptions {
+    trim: bool,
+    lowercase_keys: HashSet<Key>,
+}
+
+impl NormalizeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Strip leading and trailing whitespace from every string value.
+    pub fn trim_whitespace(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    /// Lowercase the values of the given attribute keys, e.g. `http.method`. Values of
+    /// other keys keep their casing.
+    pub fn lowercase<K: Into<Key>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.lowercase_keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    /// The normalized value, or `None` if normalization leaves it unchanged.
+    fn apply(&self, key: &Key, value: &str) -> Option<String> {
+        let trimmed = if self.trim { value.trim() } else { value };
+        if self.lowercase_keys.contains(key) && trimmed.chars().any(char::is_uppercase) {
+            return Some(trimmed.to_lowercase());
+        }
+        (trimmed.len() != value.len()).then(|| trimmed.to_string())
+    }
+}
+
+/// Type an attribute value is coerced to by
+/// [`with_type_coercion`](crate::ParseableExporterBuilder::with_type_coercion).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    Int,
+    Float,
+    Bool,
+    String,
+}
+
+impl AttributeType {
+    /// The value converted to this type, or `None` if it already has it or can't be
+    /// converted.
+    fn coerce(&self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (AttributeType::Int, Value::F64(f))
+                if f.fract() == 0.0 && f.abs() < i64::MAX as f64 =>
+            {
+                Some(Value::I64(*f as i64))
+            }
+            (AttributeType::Int, Value::String(s)) => {
+                s.as_str().trim().parse().ok().map(Value::I64)
+            }
+            (AttributeType::Float, Value::I64(i)) => Some(Value::F64(*i as f64)),
+            (AttributeType::Float, Value::String(s)) => s
+                .as_str()
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(Value::F64),
+            (AttributeType::Bool, Value::String(s)) => {
+                match s.as_str().trim().to_ascii_lowercase().as_str() {
+                    "true" => Some(Value::Bool(true)),
+                    "false" => Some(Value::Bool(false)),
+                    _ => None,
+                }
+            }
+            (AttributeType::String, Value::String(_)) => None,
+            (AttributeType::String, value) => {
+                Some(Value::String(value.as_str().into_owned().into()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Case attribute keys are written in, see
+/// [`with_attribute_key_case`](crate::ParseableExporterBuilder::with_attribute_key_case).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    /// Keys are written as the instrumentation reported them.
+    #[default]
+    AsIs,
+    /// Keys are lowercased, so `HTTP.Method` and `http.method` end up in one column.
+    Lower,
+}
+
+/// What happens to the attributes matched by
+/// [`with_redacted_keys`](crate::ParseableExporterBuilder::with_redacted_keys).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedactionMode {
+    /// The attribute is left out.
+    Drop,
+    /// The value is replaced with `***`.
+    #[default]
+    Mask,
+    /// The value is replaced with the hex encoded SHA-256 hash of its string form, so
+    /// records of the same user can still be correlated.
+    Hash,
+}
+
+/// Lowercased attribute keys whose values are redacted, and prefixes of redacted keys
+/// from patterns ending in `*`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Redaction {
+    keys: HashSet<String>,
+    prefixes: Vec<String>,
+    pub(crate) mode: RedactionMode,
+}
+
+impl Redaction {
+    pub(crate) fn new(patterns: Vec<String>, mode: RedactionMode) -> Self {
+        let mut redaction = Redaction {
+            mode,
+            ..Default::default()
+        };
+        for pattern in patterns {
+            let pattern = pattern.to_lowercase();
+            match pattern.strip_suffix('*') {
+                Some(prefix) => redaction.prefixes.push(prefix.to_string()),
+                None => {
+                    redaction.keys.insert(pattern);
+                }
+            }
+        }
+        redaction
+    }
+
+    pub(crate) fn matches(&self, key: &Key) -> bool {
+        let key = key.as_str().to_lowercase();
+        self.keys.contains(&key) || self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+    }
+
+    /// The value replacing a redacted one, unless the attribute is dropped.
+    fn replacement(&self, value: &Value) -> Option<Value> {
+        match self.mode {
+            RedactionMode::Drop => None,
+            RedactionMode::Mask => Some(Value::String("***".into())),
+            RedactionMode::Hash => {
+                let hash = Sha256::digest(value.as_str().as_bytes());
+                let mut hex = String::with_capacity(hash.len() * 2);
+                for byte in hash {
+                    let _ = write!(hex, "{byte:02x}");
+                }
+                Some(Value::String(hex.into()))
+            }
+        }
+    }
+}
+
+/// How attribute keys and values are rewritten and rendered.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AttributeOptions {
+    /// Attribute keys renamed before serialization.
+    pub(crate) key_map: Option<HashMap<Key, Key>>,
+    /// Span and event attribute keys that are exported; all others are dropped.
+    pub(crate) allowlist: Option<HashSet<Key>>,
+    pub(crate) normalize: Option<NormalizeOptions>,
+    pub(crate) coercion: Option<HashMap<Key, AttributeType>>,
+    /// Units written next to the values of these attribute keys.
+    pub(crate) units: Option<HashMap<Key, String>>,
+    /// Attribute keys whose string values are parsed as JSON objects or arrays.
+    pub(crate) json_keys: Option<HashSet<Key>>,
+    /// Array values longer than this are truncated.
+    pub(crate) max_array_elements: Option<usize>,
+    /// Render attributes as an object of typed values instead of `key=value` strings.
+    pub(crate) typed: bool,
+    pub(crate) key_case: CaseMode,
+    pub(crate) redaction: Option<Redaction>,
+}
+
+impl AttributeOptions {
+    /// Whether the attribute with the original key `key` is redacted.
+    pub(crate) fn is_redacted(&self, key: &Key) -> bool {
+        self.redaction
+            .as_ref()
+            .is_some_and(|redaction| redaction.matches(key))
+    }
+
+    fn is_dropped(&self, key: &Key) -> bool {
+        self.redaction.as_ref().is_some_and(|redaction| {
+            redaction.mode == RedactionMode::Drop && redaction.matches(key)
+        })
+    }
+
+    /// The key an attribute is serialized under.
+    fn mapped_key<'k>(&'k self, key: &'k Key) -> Cow<'k, str> {
+        let key = self
+            .key_map
+            .as_ref()
+            .and_then(|key_map| key_map.get(key))
+            .unwrap_or(key)
+            .as_str();
+        match self.key_case {
+            CaseMode::Lower if key.chars().any(char::is_uppercase) => {
+                Cow::Owned(key.to_lowercase())
+            }
+            _ => Cow::Borrowed(key),
+        }
+    }
+
+    /// The unit annotated for the attribute with the original key `key`.
+    fn unit(&self, key: &Key) -> Option<&str> {
+        let units = self.units.as_ref()?;
+        units.get(key).map(String::as_str)
+    }
+
+    /// The value of the attribute with the original key `key` parsed as a JSON object or
+    /// array, if its key is configured for parsing and the value is one.
+    fn parsed_json(&self, key: &Key, value: &Value) -> Option<serde_json::Value> {
+        if !self.json_keys.as_ref()?.contains(key) {
+            return None;
+        }
+        let Value::String(value) = value else {
+            return None;
+        };
+        serde_json::from_str(value.as_str())
+            .ok()
+            .filter(|json: &serde_json::Value| json.is_object() || json.is_array())
+    }
+}
+
+/// Attributes of a record, either as `key=value` strings or as typed values by key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Attributes {
+    Flat(Vec<Arc<str>>),
+    Typed(Vec<(Arc<str>, TypedAttribute)>),
+    Namespaced(Box<NamespacedAttributes>),
+}
+
+/// Value of a typed attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TypedAttribute {
+    Value(Value),
+    /// A string value parsed as JSON.
+    Json(serde_json::Value),
+}
+
+impl From<Value> for TypedAttribute {
+    fn from(value: Value) -> Self {
+        TypedAttribute::Value(value)
+    }
+}
+
+/// Resource, span and event attributes of a record as one object of typed maps.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct NamespacedAttributes {
+    pub(crate) resource: Attributes,
+    pub(crate) span: Attributes,
+    pub(crate) event: Attributes,
+}
+
+impl Attributes {
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            Attributes::Flat(attributes) => attributes.is_empty(),
+            Attributes::Typed(attributes) => attributes.is_empty(),
+            Attributes::Namespaced(attributes) => {
+                attributes.resource.is_empty()
+                    && attributes.span.is_empty()
+                    && attributes.event.is_empty()
+            }
+        }
+    }
+}
+
+impl Serialize for Attributes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Attributes::Flat(attributes) => attributes.serialize(serializer),
+            Attributes::Typed(attributes) => {
+                let mut map = serializer.serialize_map(Some(attributes.len()))?;
+                for (key, value) in attributes {
+                    match value {
+                        TypedAttribute::Value(value) => {
+                            map.serialize_entry(key, &TypedValue(value))?
+                        }
+                        TypedAttribute::Json(json) => map.serialize_entry(key, json)?,
+                    }
+                }
+                map.end()
+            }
+            Attributes::Namespaced(attributes) => attributes.serialize(serializer),
+        }
+    }
+}
+
+/// Serializes an attribute value as the matching JSON type.
+struct TypedValue<'a>(&'a Value);
+
+impl Serialize for TypedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn seq<S: Serializer, T: Serialize>(
+            serializer: S,
+            values: &[T],
+        ) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(values.len()))?;
+            for value in values {
+                seq.serialize_element(value)?;
+            }
+            seq.end()
+        }
+
+        match self.0 {
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::I64(i) => serializer.serialize_i64(*i),
+            Value::F64(f) => serializer.serialize_f64(*f),
+            Value::String(s) => serializer.serialize_str(s.as_str()),
+            Value::Array(Array::Bool(values)) => seq(serializer, values),
+            Value::Array(Array::I64(values)) => seq(serializer, values),
+            Value::Array(Array::F64(values)) => seq(serializer, values),
+            Value::Array(Array::String(values)) => {
+                let values: Vec<&str> = values.iter().map(|s| s.as_str()).collect();
+                seq(serializer, &values)
+            }
+        }
+    }
+}
+
+/// Builds the attribute strings of a batch, optionally sharing one allocation between
+/// identical strings.
+///
+/// Strings are formatted into a reusable buffer, so with interning enabled a string
+/// that was already seen in the batch costs no allocation at all.
+#[derive(Debug, Default)]
+pub(crate) struct StringPool {
+    interned: Option<HashSet<Arc<str>>>,
+    options: Arc<AttributeOptions>,
+    buffer: String,
+}
+
+impl StringPool {
+    pub(crate) fn new(interning: bool, options: Arc<AttributeOptions>) -> Self {
+        StringPool {
+            interned: interning.then(HashSet::new),
+            options,
+            buffer: String::new(),
+        }
+    }
+
+    fn intern(interned: &mut Option<HashSet<Arc<str>>>, string: &str) -> Arc<str> {
+        match interned {
+            Some(interned) => match interned.get(string) {
+                Some(string) => string.clone(),
+                None => {
+                    let string: Arc<str> = Arc::from(string);
+                    interned.insert(string.clone());
+                    string
+                }
+            },
+            None => Arc::from(string),
+        }
+    }
+
+    /// The value after redaction, or else normalization and type coercion, all matched on
+    /// the original key, and array truncation, along with the number of array elements
+    /// truncated.
+    fn value<'v>(&self, key: &Key, value: &'v Value) -> (Cow<'v, Value>, usize) {
+        if let Some(redaction) = &self.options.redaction {
+            if redaction.matches(key) {
+                if let Some(replacement) = redaction.replacement(value) {
+                    return (Cow::Owned(replacement), 0);
+                }
+            }
+        }
+        let value = self.coerced_value(key, value);
+        match self.options.max_array_elements {
+            Some(max) => truncate_array(value, max),
+            None => (value, 0),
+        }
+    }
+
+    fn coerced_value<'v>(&self, key: &Key, value: &'v Value) -> Cow<'v, Value> {
+        let normalized = match (&self.options.normalize, value) {
+            (Some(normalize), Value::String(s)) => normalize.apply(key, s.as_str()),
+            _ => None,
+        };
+        let value = match normalized {
+            Some(normalized) => Cow::Owned(Value::String(normalized.into())),
+            None => Cow::Borrowed(value),
+        };
+        let coerced = self
+            .options
+            .coercion
+            .as_ref()
+            .and_then(|coercion| coercion.get(key))
+            .and_then(|attribute_type| attribute_type.coerce(&value));
+        match coerced {
+            Some(coerced) => Cow::Owned(coerced),
+            None => value,
+        }
+    }
+
+    fn push_attribute(&mut self, key: &Key, value: &Value, attributes: &mut Vec<Arc<str>>) {
+        let (value, truncated) = self.value(key, value);
+        let unit = self.options.unit(key);
+        let json = self.options.parsed_json(key, &value);
+        let key = self.options.mapped_key(key);
+        match json {
+            Some(serde_json::Value::Object(object)) => Self::push_flattened_json(
+                &mut self.interned,
+                &mut self.buffer,
+                &key,
+                &object,
+                attributes,
+            ),
+            _ => {
+                self.buffer.clear();
+                let _ = write!(self.buffer, "{key}={value}");
+                attributes.push(Self::intern(&mut self.interned, &self.buffer));
+            }
+        }
+        if truncated > 0 {
+            self.buffer.clear();
+            let _ = write!(self.buffer, "{key}{TRUNCATED_SUFFIX}={truncated}");
+            attributes.push(Self::intern(&mut self.interned, &self.buffer));
+        }
+        if let Some(unit) = unit {
+            self.buffer.clear();
+            let _ = write!(self.buffer, "{key}{UNIT_SUFFIX}={unit}");
+            attributes.push(Self::intern(&mut self.interned, &self.buffer));
+        }
+    }
+
+    /// Push a `<key>.<field>=<value>` string per leaf of `object`, joining the keys of
+    /// nested objects with dots. Arrays are leaves, written as JSON.
+    fn push_flattened_json(
+        interned: &mut Option<HashSet<Arc<str>>>,
+        buffer: &mut String,
+        key: &str,
+        object: &serde_json::Map<String, serde_json::Value>,
+        attributes: &mut Vec<Arc<str>>,
+    ) {
+        for (field, value) in object {
+            buffer.clear();
+            let _ = match value {
+                serde_json::Value::Object(object) => {
+                    let key = format!("{key}.{field}");
+                    Self::push_flattened_json(interned, buffer, &key, object, attributes);
+                    continue;
+                }
+                serde_json::Value::String(value) => write!(buffer, "{key}.{field}={value}"),
+                value => write!(buffer, "{key}.{field}={value}"),
+            };
+            attributes.push(Self::intern(interned, buffer));
+        }
+    }
+
+    fn push_typed_attribute(
+        &mut self,
+        key: &Key,
+        value: &Value,
+        attributes: &mut Vec<(Arc<str>, TypedAttribute)>,
+    ) {
+        let (value, truncated) = self.value(key, value);
+        let value = match self.options.parsed_json(key, &value) {
+            Some(json) => TypedAttribute::Json(json),
+            None => TypedAttribute::Value(value.into_owned()),
+        };
+        let unit = self.options.unit(key);
+        let key = self.options.mapped_key(key);
+        attributes.push((Self::intern(&mut self.interned, &key), value));
+        if truncated > 0 {
+            let marker = format!("{key}{TRUNCATED_SUFFIX}");
+            attributes.push((
+                Self::intern(&mut self.interned, &marker),
+                Value::I64(truncated as i64).into(),
+            ));
+        }
+        if let Some(unit) = unit {
+            let companion = format!("{key}{UNIT_SUFFIX}");
+            attributes.push((
+                Self::intern(&mut self.interned, &companion),
+                Value::String(unit.to_string().into()).into(),
+            ));
+        }
+    }
+}
+
+/// Like [`extract_attributes`], minus the span or event attributes missing from the
+/// allowlist.
+pub(crate) fn extract_span_attributes<'a>(
+    attributes: impl Iterator<Item = (&'a Key, &'a Value)>,
+    pool: &mut StringPool,
+) -> Attributes {
+    let options = pool.options.clone();
+    extract_attributes(
+        attributes.filter(|(key, _)| {
+            options
+                .allowlist
+                .as_ref()
+                .is_none_or(|allowlist| allowlist.contains(*key))
+        }),
+        pool,
+    )
+}
+
+pub(crate) fn extract_attributes<'a>(
+    attributes: impl Iterator<Item = (&'a Key, &'a Value)>,
+    pool: &mut StringPool,
+) -> Attributes {
+    let options = pool.options.clone();
+    let attributes = attributes.filter(|(key, _)| !options.is_dropped(key));
+    if pool.options.key_case != CaseMode::AsIs {
+        // Keys differing only in case collide once normalized. Attribute maps have no
+        // order, so the winner is picked by key: one already in the normalized case,
+        // otherwise the lexicographically smallest.
+        let mut unique: Vec<(&Key, &Value)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (key, value) in attributes {
+            let normalized = pool.options.mapped_key(key).into_owned();
+            match positions.entry(normalized) {
+                Entry::Occupied(position) => {
+                    let existing = &mut unique[*position.get()];
+                    let normalized = position.key().as_str();
+                    let rank = |key: &'a Key| (key.as_str() != normalized, key.as_str());
+                    if rank(key) < rank(existing.0) {
+                        *existing = (key, value);
+                    }
+                }
+                Entry::Vacant(position) => {
+                    position.insert(unique.len());
+                    unique.push((key, value));
+                }
+            }
+        }
+        return render_attributes(unique.into_iter(), pool);
+    }
+    render_attributes(attributes, pool)
+}
+
+fn render_attributes<'a>(
+    attributes: impl Iterator<Item = (&'a Key, &'a Value)>,
+    pool: &mut StringPool,
+) -> Attributes {
+    if pool.options.typed {
+        let mut typed = Vec::new();
+        for (key, value) in attributes {
+            pool.push_typed_attribute(key, value, &mut typed);
+        }
+        Attributes::Typed(typed)
+    } else {
+        let mut flat = Vec::new();
+        for (key, value) in attributes {
+            pool.push_attribute(key, value, &mut flat);
+        }
+        Attributes::Flat(flat)
+    }
+}
+
+/// Suffix of the key of the attribute flagging how many elements were truncated from an
+/// array value.
+const TRUNCATED_SUFFIX: &str = ".truncated";
+
+/// Suffix of the key of the attribute holding the unit of an annotated attribute.
+const UNIT_SUFFIX: &str = "_unit";
+
+/// The array truncated to `max` elements, with the number of elements dropped.
+fn truncate_array(value: Cow<'_, Value>, max: usize) -> (Cow<'_, Value>, usize) {
+    fn truncated<T: Clone>(values: &[T], max: usize) -> Option<(Vec<T>, usize)> {
+        (values.len() > max).then(|| (values[..max].to_vec(), values.len() - max))
+    }
+
+    let truncated = match value.as_ref() {
+        Value::Array(Array::Bool(values)) => {
+            truncated(values, max).map(|(v, n)| (Array::Bool(v), n))
+        }
+        Value::Array(Array::I64(values)) => truncated(values, max).map(|(v, n)| (Array::I64(v), n)),
+        Value::Array(Array::F64(values)) => truncated(values, max).map(|(v, n)| (Array::F64(v), n)),
+        Value::Array(Array::String(values)) => {
+            truncated(values, max).map(|(v, n)| (Array::String(v), n))
+        }
+        _ => None,
+    };
+    match truncated {
+        Some((array, dropped)) => (Cow::Owned(Value::Array(array)), dropped),
+        None => (value, 0),
+    }
+}
@@ -0,0 +1,82 @@
+use opentelemetry::sdk::trace::BatchConfig;
+use std::{env, str::FromStr, time::Duration};
+
+/// Telemetry signal a batch configuration applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Traces,
+    Logs,
+    Metrics,
+}
+
+impl Signal {
+    fn env_prefix(&self) -> &'static str {
+        match self {
+            Signal::Traces => "OTLP_TRACES",
+            Signal::Logs => "OTLP_LOGS",
+            Signal::Metrics => "OTLP_METRICS",
+        }
+    }
+
+    /// Read `OTLP_<SIGNAL>_<NAME>`, falling back to the generic `OTLP_<NAME>`.
+    fn env_var<T: FromStr>(&self, name: &str) -> Option<T> {
+        env::var(format!("{}_{}", self.env_prefix(), name))
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .or_else(|| {
+                env::var(format!("OTLP_{name}"))
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+            })
+    }
+}
+
+/// Batching settings of a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSettings {
+    pub max_queue_size: usize,
+    pub max_export_batch_size: usize,
+    pub scheduled_delay: Duration,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        BatchSettings {
+            max_queue_size: 65536,
+            max_export_batch_size: 8192,
+            scheduled_delay: Duration::from_millis(1000),
+        }
+    }
+}
+
+impl BatchSettings {
+    /// Settings for `signal` from the environment. Signal specific variables such as
+    /// `OTLP_TRACES_BATCH_SIZE` take precedence over the generic `OTLP_BATCH_SIZE`.
+    pub fn from_env(signal: Signal) -> Self {
+        BatchSettings::default().overridden_by_env(signal)
+    }
+
+    /// These settings, with the values set for `signal` in the environment taking
+    /// precedence.
+    pub fn overridden_by_env(self, signal: Signal) -> Self {
+        BatchSettings {
+            max_queue_size: signal.env_var("QUEUE_SIZE").unwrap_or(self.max_queue_size),
+            max_export_batch_size: signal
+                .env_var("BATCH_SIZE")
+                .unwrap_or(self.max_export_batch_size),
+            scheduled_delay: signal
+                .env_var("INTERVAL_MILLIS")
+                .map(Duration::from_millis)
+                .unwrap_or(self.scheduled_delay),
+        }
+    }
+}
+
+impl From<BatchSettings> for BatchConfig {
+    fn from(settings: BatchSettings) -> Self {
+        BatchConfig::default()
+            .with_max_queue_size(settings.max_queue_size)
+            .with_max_export_batch_size(settings.max_export_batch_size)
+            .with_scheduled_delay(settings.scheduled_delay)
+    }
+}
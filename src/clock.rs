@@ -0,0 +1,29 @@
+use std::{fmt::Debug, time::SystemTime};
+
+/// Source of the current time for the time-dependent behavior of the exporter, such as
+/// working out how long to wait for a `Retry-After` date, and of the timestamps of
+/// exported log events. Span timestamps come from the spans themselves and never from
+/// the clock.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock, used unless another clock is configured.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock standing still at the given time, for deterministic tests.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{BatchSettings, Compression, ParseableExporterBuilder};
+use opentelemetry::trace::TraceError;
+use serde::Deserialize;
+use std::{env, fs, path::Path, time::Duration};
+
+/// Exporter configuration read by
+/// [`from_config_file`](ParseableExporterBuilder::from_config_file). Every key is
+/// optional, unknown keys are rejected.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    host: Option<String>,
+    port: Option<u16>,
+    tls: Option<bool>,
+    username: Option<String>,
+    password: Option<String>,
+    stream: Option<String>,
+    batch: Option<BatchSection>,
+    export: ExportSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BatchSection {
+    queue_size: Option<usize>,
+    batch_size: Option<usize>,
+    interval_millis: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ExportSection {
+    compression: Option<String>,
+    compression_threshold: Option<usize>,
+    max_connections: Option<usize>,
+    byte_flush: Option<usize>,
+    max_buffered_batches: Option<usize>,
+    send_queue: Option<usize>,
+    shutdown_grace_period_millis: Option<u64>,
+    exporter_id: bool,
+    stream_field: bool,
+    resource_as_headers: bool,
+    publish_schema: bool,
+    string_interning: bool,
+    typed_attributes: bool,
+    numeric_status: bool,
+    sort_by_end_time: bool,
+    diagnostics: bool,
+}
+
+/// Parse the configuration file at `path`, as TOML or JSON depending on its extension.
+fn read(path: &Path) -> Result<FileConfig, TraceError> {
+    let invalid = |reason: String| {
+        TraceError::from(format!(
+            "invalid exporter config file {}: {reason}",
+            path.display()
+        ))
+    };
+    let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|e| invalid(e.to_string())),
+        Some("json") => serde_json::from_str(&contents).map_err(|e| invalid(e.to_string())),
+        _ => Err(invalid("expected a .toml or .json file".into())),
+    }
+}
+
+fn parse_compression(name: &str) -> Result<Compression, TraceError> {
+    match name {
+        "none" => Ok(Compression::None),
+        "gzip" => Ok(Compression::Gzip),
+        #[cfg(feature = "zstd")]
+        "zstd" => Ok(Compression::Zstd),
+        #[cfg(feature = "brotli")]
+        "brotli" => Ok(Compression::Brotli),
+        _ => Err(TraceError::from(format!(
+            "unsupported compression '{name}', the crate may need to be built with the matching feature"
+        ))),
+    }
+}
+
+/// Replace `value` with the file's, unless the environment variable `var` is set.
+fn set_unless_env<T>(value: &mut T, var: &str, from_file: Option<T>) {
+    if let Some(from_file) = from_file {
+        if env::var_os(var).is_none() {
+            *value = from_file;
+        }
+    }
+}
+
+impl ParseableExporterBuilder {
+    /// Builder configured from a TOML or JSON file, chosen by the file's extension. The
+    /// connection settings (`host`, `port`, `tls`, `username`, `password`, `stream`) are
+    /// top level keys, batch settings go in a `batch` table (`queue_size`, `batch_size`,
+    /// `interval_millis`) and the options of the `with_*` methods in an `export` table,
+    /// e.g. `compression = "gzip"` or `exporter_id = true`.
+    ///
+    /// `PARSEABLE_*` and `OTLP_*` environment variables take precedence over the values
+    /// in the file. Unknown keys and values of the wrong type are rejected with an error
+    /// naming them. Builder methods called on the result override the file as usual.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self, TraceError> {
+        let config = read(path.as_ref())?;
+        let mut builder = ParseableExporterBuilder::default();
+        set_unless_env(&mut builder.host, "PARSEABLE_HOST", config.host);
+        set_unless_env(
+            &mut builder.port,
+            "PARSEABLE_PORT",
+            config.port.map(|port| port.to_string()),
+        );
+        set_unless_env(&mut builder.username, "PARSEABLE_USERNAME", config.username);
+        set_unless_env(&mut builder.password, "PARSEABLE_PASSWORD", config.password);
+        builder.tls_enabled = config.tls.unwrap_or(builder.tls_enabled);
+        builder.service_name = config.stream.unwrap_or(builder.service_name);
+        if let Some(batch) = config.batch {
+            let defaults = BatchSettings::default();
+            builder.batch_settings = Some(BatchSettings {
+                max_queue_size: batch.queue_size.unwrap_or(defaults.max_queue_size),
+                max_export_batch_size: batch.batch_size.unwrap_or(defaults.max_export_batch_size),
+                scheduled_delay: batch
+                    .interval_millis
+                    .map(Duration::from_millis)
+                    .unwrap_or(defaults.scheduled_delay),
+            });
+        }
+
+        let export = config.export;
+        if let Some(compression) = export.compression {
+            builder.compression = parse_compression(&compression)?;
+        }
+        builder.compression_threshold = export
+            .compression_threshold
+            .unwrap_or(builder.compression_threshold);
+        builder.max_connections_per_host = export.max_connections;
+        builder.byte_flush = export.byte_flush;
+        builder.max_buffered_batches = export.max_buffered_batches;
+        builder.send_queue = export.send_queue;
+        if let Some(millis) = export.shutdown_grace_period_millis {
+            builder.shutdown_grace_period = Duration::from_millis(millis);
+        }
+        builder.exporter_id = export.exporter_id;
+        builder.stream_field = export.stream_field;
+        builder.resource_as_headers = export.resource_as_headers;
+        builder.publish_schema = export.publish_schema;
+        builder.string_interning = export.string_interning;
+        builder.typed_attributes = export.typed_attributes;
+        builder.numeric_status = export.numeric_status;
+        builder.sort_by_end_time = export.sort_by_end_time;
+        // OTLP_DEBUG=1 enables diagnostics even when the file doesn't.
+        builder.diagnostics |= export.diagnostics;
+        Ok(builder)
+    }
+}
@@ -0,0 +1,81 @@
+use http::{header::CONTENT_ENCODING, HeaderMap, StatusCode};
+
+/// Header name fragments whose values are never logged.
+const SENSITIVE_HEADERS: [&str; 6] = [
+    "authorization",
+    "cookie",
+    "token",
+    "secret",
+    "password",
+    "key",
+];
+
+/// Logs rejected ingest requests in full, for debugging ingestion failures.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Diagnostics {
+    /// Logged request bodies are truncated to this many bytes.
+    pub(crate) max_body_len: usize,
+}
+
+impl Diagnostics {
+    /// Describe the headers and body of a request before it is sent, with credentials
+    /// masked.
+    pub(crate) fn describe_request(&self, request: &reqwest::Request) -> String {
+        let headers = redacted_headers(request.headers());
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        let body = match request.headers().get(CONTENT_ENCODING) {
+            Some(encoding) => format!("<{} bytes, {:?} encoded>", body.len(), encoding),
+            None => truncate(
+                String::from_utf8_lossy(body).into_owned(),
+                self.max_body_len,
+            ),
+        };
+        format!(
+            "{} {} headers: {headers} body: {body}",
+            request.method(),
+            request.url()
+        )
+    }
+
+    pub(crate) fn log_rejection(&self, request: &str, status: StatusCode, response_body: &str) {
+        tracing::error!(
+            %status,
+            response_body,
+            request,
+            "Parseable rejected an ingest request"
+        );
+    }
+}
+
+fn redacted_headers(headers: &HeaderMap) -> String {
+    let headers: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str();
+            if SENSITIVE_HEADERS
+                .iter()
+                .any(|sensitive| name.contains(sensitive))
+            {
+                format!("{name}: <redacted>")
+            } else {
+                format!("{name}: {}", String::from_utf8_lossy(value.as_bytes()))
+            }
+        })
+        .collect();
+    format!("{{{}}}", headers.join(", "))
+}
+
+/// Truncate `text` to at most `max_len` bytes, on a char boundary.
+pub(crate) fn truncate(mut text: String, max_len: usize) -> String {
+    if text.len() > max_len {
+        let mut end = max_len;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
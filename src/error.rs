@@ -0,0 +1,82 @@
+use http::StatusCode;
+use opentelemetry::trace::TraceError;
+use std::{fmt, sync::Arc};
+
+/// A failed export, as passed to the handler registered with
+/// [`with_error_handler`](crate::ParseableExporterBuilder::with_error_handler).
+#[derive(Debug, Clone)]
+pub struct ParseableExporterError {
+    /// Description of the failure, the same as the error returned to the span processor.
+    pub message: String,
+    /// Status Parseable responded with, if the request got a response at all.
+    pub status: Option<StatusCode>,
+    /// Start of the body Parseable responded with, for unsuccessful statuses.
+    pub body: Option<String>,
+    /// Stream the batch was sent to.
+    pub stream: String,
+    /// Number of records in the failed batch.
+    pub message_count: usize,
+}
+
+impl fmt::Display for ParseableExporterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to export {} records to stream '{}': {}",
+            self.message_count, self.stream, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseableExporterError {}
+
+/// Callback invoked once for every failed export.
+#[derive(Clone)]
+pub(crate) struct ErrorHandler(pub(crate) Arc<dyn Fn(ParseableExporterError) + Send + Sync>);
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorHandler")
+    }
+}
+
+/// Error of a single ingest request, along with the response if there was one.
+#[derive(Debug)]
+pub(crate) struct SendFailure {
+    pub(crate) error: TraceError,
+    pub(crate) status: Option<StatusCode>,
+    pub(crate) body: Option<String>,
+}
+
+impl SendFailure {
+    /// Report the failure of a batch of `message_count` records sent to `stream` to the
+    /// error handler, if there is one.
+    pub(crate) fn report(
+        &self,
+        handler: Option<&ErrorHandler>,
+        stream: String,
+        message_count: usize,
+    ) -> ParseableExporterError {
+        let error = ParseableExporterError {
+            message: self.error.to_string(),
+            status: self.status,
+            body: self.body.clone(),
+            stream,
+            message_count,
+        };
+        if let Some(ErrorHandler(handler)) = handler {
+            handler(error.clone());
+        }
+        error
+    }
+}
+
+impl From<TraceError> for SendFailure {
+    fn from(error: TraceError) -> Self {
+        SendFailure {
+            error,
+            status: None,
+            body: None,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+/// Counts the exports whose requests haven't completed yet.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    count: Mutex<usize>,
+    idle: Condvar,
+}
+
+impl InFlight {
+    /// Register an export, which stays in flight until the guard is dropped.
+    pub(crate) fn start(self: &Arc<Self>) -> InFlightGuard {
+        *self.count.lock().unwrap() += 1;
+        InFlightGuard(self.clone())
+    }
+
+    /// Like [`start`](Self::start), unless `limit` exports are in flight already.
+    pub(crate) fn try_start(self: &Arc<Self>, limit: usize) -> Option<InFlightGuard> {
+        let mut count = self.count.lock().unwrap();
+        if *count >= limit {
+            return None;
+        }
+        *count += 1;
+        Some(InFlightGuard(self.clone()))
+    }
+
+    /// Block until no export is in flight or `timeout` elapses, returning whether all
+    /// exports completed.
+    pub(crate) fn wait_idle(&self, timeout: Duration) -> bool {
+        let count = self.count.lock().unwrap();
+        let (count, _) = self
+            .idle
+            .wait_timeout_while(count, timeout, |count| *count > 0)
+            .unwrap();
+        *count == 0
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct InFlightGuard(Arc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut count = self.0.count.lock().unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
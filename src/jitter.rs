@@ -0,0 +1,52 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// How the backoff before retrying a request is randomized, both for requests that
+/// failed without a response or with a 5xx status and for rate limited ones, so
+/// exporters failing or rate limited at the same time don't all retry at the same time.
+///
+/// The strategies are the ones described in
+/// <https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/>, starting
+/// from an exponential backoff of `base * 2^attempt` capped at `cap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JitterStrategy {
+    /// The backoff itself.
+    None,
+    /// Anywhere between zero and the backoff.
+    #[default]
+    Full,
+    /// Half of the backoff, plus anywhere between zero and the other half.
+    Equal,
+    /// Anywhere between `base` and three times the previous delay, capped at `cap`.
+    Decorrelated,
+}
+
+impl JitterStrategy {
+    /// The delay before retry `attempt`, counted from 0, after waiting `previous` before
+    /// the last attempt, or `base` for the first retry.
+    pub fn delay(
+        &self,
+        base: Duration,
+        cap: Duration,
+        attempt: u32,
+        previous: Duration,
+    ) -> Duration {
+        let backoff = base
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(cap)
+            .min(cap);
+        let mut rng = rand::thread_rng();
+        match self {
+            JitterStrategy::None => backoff,
+            JitterStrategy::Full => rng.gen_range(Duration::ZERO..=backoff),
+            JitterStrategy::Equal => {
+                let half = backoff / 2;
+                half + rng.gen_range(Duration::ZERO..=backoff - half)
+            }
+            JitterStrategy::Decorrelated => {
+                let upper = previous.saturating_mul(3).max(base);
+                rng.gen_range(base..=upper).min(cap)
+            }
+        }
+    }
+}
@@ -1,96 +1,848 @@
+mod attributes;
+mod batch;
+mod clock;
+mod compression;
+mod config;
+mod diagnostics;
+mod error;
+mod in_flight;
+mod jitter;
+mod logs;
+mod metrics;
+mod processor;
+mod resource;
+mod routing;
+mod runtime;
+mod schema;
+mod send_queue;
+mod startup;
+mod stats;
+
+pub use attributes::{AttributeType, CaseMode, NormalizeOptions, RedactionMode};
+pub use batch::{BatchSettings, Signal};
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use compression::Compression;
+pub use error::ParseableExporterError;
+pub use jitter::JitterStrategy;
+pub use logs::ParseableLogLayer;
+pub use metrics::ParseableMetricsExporter;
+pub use processor::ByteBoundedProcessor;
+pub use resource::{CachedDetector, EnvVarDetector};
+pub use stats::ExporterStats;
+
 use base64::{engine::general_purpose as base64encoder, Engine};
 use chrono::{DateTime, Utc};
 use futures_core::future::BoxFuture;
-use http::{HeaderMap, HeaderValue, Method};
+use http::{
+    header::{HeaderName, CONTENT_ENCODING},
+    HeaderMap, HeaderValue, Method, StatusCode,
+};
 use opentelemetry::{
     global,
     sdk::{
         self,
         export::{self, trace::SpanData},
-        trace::{BatchConfig, BatchSpanProcessor, TraceRuntime},
+        trace::{BatchConfig, BatchSpanProcessor, SpanProcessor, TraceRuntime},
     },
-    trace::{TraceError, TracerProvider},
-    Key, Value,
+    trace::{SpanId, SpanKind, Status, TraceError, TraceId, TraceResult, TracerProvider},
+    Key, KeyValue, Value,
 };
 
-use reqwest::Url;
-use serde::Serialize;
+use attributes::{
+    extract_attributes, extract_span_attributes, AttributeOptions, Attributes,
+    NamespacedAttributes, Redaction, StringPool,
+};
+use diagnostics::{truncate, Diagnostics};
+use error::{ErrorHandler, SendFailure};
+use in_flight::{InFlight, InFlightGuard};
+use processor::{BoxedProcessor, CorrelationIdProcessor, CORRELATION_ID_ATTRIBUTE};
+use reqwest::{redirect, Url};
+use routing::{StreamRouter, TargetStreams};
+use schema::SchemaPublisher;
+use send_queue::{QueuedRequest, SendQueue};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use stats::ExportStats;
 use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
     env,
     fmt::Debug,
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::{Duration, SystemTime},
 };
-
-/// Get configuration options for batch exporter
-fn get_batch_config() -> BatchConfig {
-    BatchConfig::default()
-        .with_max_queue_size(
-            env::var("OTLP_QUEUE_SIZE")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(65536),
-        )
-        .with_max_export_batch_size(
-            env::var("OTLP_BATCH_SIZE")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(8192),
-        )
-        .with_scheduled_delay(Duration::from_millis(
-            env::var("OTLP_INTERVAL_MILLIS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(1000),
-        ))
-}
+use tokio::sync::Semaphore;
+use uuid::Uuid;
 
 /// A message is a single data request sent to Parseable WS and represents a single event collected.
 /// So, from a single SpanData we create multiple ParseableMessage(s)
 #[derive(Serialize, Debug, Clone)]
 struct TraceMessage {
-    resource_attributes: Vec<String>,
+    #[serde(skip_serializing_if = "Nullable::is_omitted")]
+    resource_attributes: Nullable<Attributes>,
     span_name: String,
-    attributes: Vec<String>,
+    /// Lowercase name of the span's kind, e.g. `server`.
+    span_kind: &'static str,
+    attributes: Attributes,
     start_time: String,
     end_time: String,
+    #[serde(flatten)]
+    duration: SpanDuration,
     parent_span_id: String,
     span_id: String,
     trace_id: String,
-    event_message: Option<String>,
-    event_timestamp: Option<String>,
+    /// The span's W3C `tracestate` header value, empty when it carries none.
+    trace_state: String,
+    /// Whether the span was sampled, i.e. the sampled bit of its trace flags.
+    sampled: bool,
+    links: Vec<SpanLink>,
+    /// Attributes and events the span limits dropped from the span.
+    dropped_attributes_count: u32,
+    dropped_events_count: u32,
+    /// Whether the span is the first of its trace in this service, if enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_root: Option<bool>,
+    /// `UNSET`, `OK` or `ERROR`, or the numeric status code.
+    status_code: SpanStatus,
+    /// Description of an error status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_message: Option<String>,
+    #[serde(skip_serializing_if = "Nullable::is_omitted")]
+    event_message: Nullable<String>,
+    #[serde(skip_serializing_if = "Nullable::is_omitted")]
+    event_timestamp: Nullable<String>,
+    #[serde(skip_serializing_if = "Nullable::is_omitted")]
+    event_attributes: Nullable<Attributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exporter_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<String>,
 }
 
-#[derive(Debug)]
+impl TraceMessage {
+    /// Move the resource, span and event attributes into one `attributes` object, with
+    /// an empty object for each that has none.
+    fn namespace_attributes(&mut self) {
+        let take =
+            |field: &mut Nullable<Attributes>| match std::mem::replace(field, Nullable::Omitted) {
+                Nullable::Value(attributes) => attributes,
+                Nullable::Null | Nullable::Omitted => Attributes::Typed(Vec::new()),
+            };
+        let resource = take(&mut self.resource_attributes);
+        let event = take(&mut self.event_attributes);
+        let span = std::mem::replace(&mut self.attributes, Attributes::Typed(Vec::new()));
+        self.attributes = Attributes::Namespaced(Box::new(NamespacedAttributes {
+            resource,
+            span,
+            event,
+        }));
+    }
+}
+
+/// A batch of records with the fields they have in common hoisted into `meta`.
+#[derive(Serialize)]
+struct Envelope<'a> {
+    meta: EnvelopeMeta,
+    records: &'a [TraceMessage],
+}
+
+#[derive(Serialize)]
+struct EnvelopeMeta {
+    #[serde(skip_serializing_if = "Nullable::is_omitted")]
+    resource_attributes: Nullable<Attributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exporter_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<String>,
+}
+
+impl EnvelopeMeta {
+    /// Move the fields all of `traces` share out of them. The exporter id and stream are
+    /// the same for every message of a request; resource attributes are only moved when
+    /// no message differs.
+    fn hoist(traces: &mut [TraceMessage]) -> Self {
+        let shared_resource = traces
+            .windows(2)
+            .all(|pair| pair[0].resource_attributes == pair[1].resource_attributes);
+        let meta = match traces.first() {
+            Some(first) => EnvelopeMeta {
+                resource_attributes: if shared_resource {
+                    first.resource_attributes.clone()
+                } else {
+                    Nullable::Omitted
+                },
+                exporter_id: first.exporter_id.clone(),
+                stream: first.stream.clone(),
+            },
+            None => EnvelopeMeta {
+                resource_attributes: Nullable::Omitted,
+                exporter_id: None,
+                stream: None,
+            },
+        };
+        for trace in traces {
+            if shared_resource {
+                trace.resource_attributes = Nullable::Omitted;
+            }
+            trace.exporter_id = None;
+            trace.stream = None;
+        }
+        meta
+    }
+}
+
+/// Static schema columns of a [`TraceMessage`] produced with the given options. Keep in
+/// sync with the struct fields above.
+fn trace_message_schema(options: &MessageOptions) -> Vec<(&'static str, &'static str)> {
+    let mut fields = vec![
+        ("span_name", "string"),
+        ("span_kind", "string"),
+        ("start_time", "datetime"),
+        ("end_time", "datetime"),
+        (options.duration_unit.field_name(), "int"),
+        ("parent_span_id", "string"),
+        ("span_id", "string"),
+        ("trace_id", "string"),
+        ("trace_state", "string"),
+        ("sampled", "boolean"),
+        // Links are objects; Parseable keeps the list as its JSON text.
+        ("links", "string"),
+        ("dropped_attributes_count", "int"),
+        ("dropped_events_count", "int"),
+        (
+            "status_code",
+            if options.numeric_status {
+                "int"
+            } else {
+                "string"
+            },
+        ),
+        ("status_message", "string"),
+        ("event_message", "string"),
+        ("event_timestamp", "datetime"),
+    ];
+    // Typed attributes are objects whose columns depend on the attributes seen.
+    if !options.attributes.typed {
+        fields.push(("resource_attributes", "string_list"));
+        fields.push(("attributes", "string_list"));
+        fields.push(("event_attributes", "string_list"));
+    }
+    if options.exporter_id.is_some() {
+        fields.push(("exporter_id", "string"));
+    }
+    if options.stream.is_some() {
+        fields.push(("stream", "string"));
+    }
+    if options.local_root {
+        fields.push(("local_root", "boolean"));
+    }
+    if options.correlation_id {
+        fields.push(("correlation_id", "string"));
+    }
+    fields
+}
+
+/// How optional record fields without a value, such as `event_message` on spans without
+/// events, are serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullHandling {
+    /// Leave the field out of the record.
+    #[default]
+    OmitNull,
+    /// Write the field as JSON `null`, so every record has the same set of columns.
+    EmitNull,
+}
+
+/// What an export does with a batch holding spans of several `service.name`s, e.g. from a
+/// tracer provider shared between services.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MixedServices {
+    /// Send the batch to the exporter's stream and report it to the global error handler,
+    /// once per set of services.
+    #[default]
+    Warn,
+    /// Send the spans of each service to the stream named after it. Spans without a
+    /// `service.name` go to the exporter's stream.
+    Split,
+}
+
+/// Credentials sent with every request in the `Authorization` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// Parseable's own users, sent as `Basic base64(username:password)`.
+    Basic { username: String, password: String },
+    /// A token sent as `Bearer <token>`, e.g. for an OAuth proxy in front of Parseable.
+    Bearer(String),
+    /// No `Authorization` header, for proxies authenticating requests some other way.
+    None,
+}
+
+/// Optional record field, serialized according to a [`NullHandling`] when it has no value.
+#[derive(Debug, Clone, PartialEq)]
+enum Nullable<T> {
+    Value(T),
+    Null,
+    Omitted,
+}
+
+impl<T> Nullable<T> {
+    fn new(value: Option<T>, handling: NullHandling) -> Self {
+        match (value, handling) {
+            (Some(value), _) => Nullable::Value(value),
+            (None, NullHandling::EmitNull) => Nullable::Null,
+            (None, NullHandling::OmitNull) => Nullable::Omitted,
+        }
+    }
+
+    fn is_omitted(&self) -> bool {
+        matches!(self, Nullable::Omitted)
+    }
+}
+
+impl<T: Serialize> Serialize for Nullable<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Nullable::Value(value) => value.serialize(serializer),
+            Nullable::Null | Nullable::Omitted => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Status of a span, serialized as its name or as the OpenTelemetry status code.
+#[derive(Debug, Clone, Copy)]
+struct SpanStatus {
+    /// 0 for unset, 1 for ok and 2 for error.
+    code: u8,
+    numeric: bool,
+}
+
+impl SpanStatus {
+    fn new(status: &Status, numeric: bool) -> Self {
+        let code = match status {
+            Status::Unset => 0,
+            Status::Ok => 1,
+            Status::Error { .. } => 2,
+        };
+        SpanStatus { code, numeric }
+    }
+}
+
+impl Serialize for SpanStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.numeric {
+            return serializer.serialize_u8(self.code);
+        }
+        serializer.serialize_str(match self.code {
+            0 => "UNSET",
+            1 => "OK",
+            _ => "ERROR",
+        })
+    }
+}
+
+/// A link from a span to another one, e.g. the spans a batch job was started by.
+#[derive(Serialize, Debug, Clone)]
+struct SpanLink {
+    trace_id: String,
+    span_id: String,
+    attributes: Attributes,
+}
+
+/// Unit of the integer span duration emitted with every record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DurationUnit {
+    #[default]
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl DurationUnit {
+    /// Name of the record field carrying the duration in this unit.
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            DurationUnit::Millis => "duration_ms",
+            DurationUnit::Micros => "duration_us",
+            DurationUnit::Nanos => "duration_ns",
+        }
+    }
+
+    fn convert(&self, duration: Duration) -> u64 {
+        let value = match self {
+            DurationUnit::Millis => duration.as_millis(),
+            DurationUnit::Micros => duration.as_micros(),
+            DurationUnit::Nanos => duration.as_nanos(),
+        };
+        value.try_into().unwrap_or(u64::MAX)
+    }
+}
+
+/// Span duration, serialized as a single integer field named after its unit.
+#[derive(Debug, Clone, Copy)]
+struct SpanDuration {
+    unit: DurationUnit,
+    value: u64,
+}
+
+impl SpanDuration {
+    /// Duration between start and end, clamped to zero if the span ends before it starts.
+    fn between(start: SystemTime, end: SystemTime, unit: DurationUnit) -> Self {
+        let duration = end.duration_since(start).unwrap_or_default();
+        SpanDuration {
+            unit,
+            value: unit.convert(duration),
+        }
+    }
+}
+
+impl Serialize for SpanDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.unit.field_name(), &self.value)?;
+        map.end()
+    }
+}
+
+/// Fractional digits of the timestamps of spans, events, logs and metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    #[default]
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimestampPrecision {
+    pub(crate) fn format(&self, timestamp: SystemTime) -> String {
+        let format = match self {
+            TimestampPrecision::Millis => chrono::SecondsFormat::Millis,
+            TimestampPrecision::Micros => chrono::SecondsFormat::Micros,
+            TimestampPrecision::Nanos => chrono::SecondsFormat::Nanos,
+        };
+        DateTime::<Utc>::from(timestamp).to_rfc3339_opts(format, true)
+    }
+}
+
+/// Options controlling how spans are flattened into [`TraceMessage`]s.
+#[derive(Debug, Clone, Default)]
+struct MessageOptions {
+    /// Resource attributes already sent as `X-P-Meta-*` headers, left out of the body of
+    /// spans whose resource has the same value.
+    header_resource: Option<HashMap<Key, Value>>,
+    /// Resource attribute keys written to `resource_attributes`, all if unset.
+    resource_keys: Option<HashSet<Key>>,
+    /// Identifier of the exporter instance, stamped on every message.
+    exporter_id: Option<String>,
+    /// Stream the messages are sent to, stamped on every message.
+    stream: Option<String>,
+    duration_unit: DurationUnit,
+    timestamp_precision: TimestampPrecision,
+    null_handling: NullHandling,
+    /// Serialize the span status as its numeric code.
+    numeric_status: bool,
+    /// Share one allocation between identical attribute strings of a batch.
+    string_interning: bool,
+    /// Emit the spans of a batch in order of their end time.
+    sort_by_end_time: bool,
+    /// Kinds of the spans exported, all kinds if unset.
+    span_kinds: Option<Vec<SpanKind>>,
+    /// Spans more than this many levels below their trace's root are dropped.
+    max_trace_depth: Option<usize>,
+    /// Flag the spans whose parent isn't part of the batch as local roots.
+    local_root: bool,
+    /// Move the correlation id captured from the baggage into `correlation_id`.
+    correlation_id: bool,
+    /// Nest the resource, span and event attributes under one `attributes` object.
+    namespaced_attributes: bool,
+    /// Send a batch as an [`Envelope`] instead of an array of records.
+    envelope: bool,
+    /// Attribute keys renamed before serialization.
+    attributes: Arc<AttributeOptions>,
+}
+
+impl MessageOptions {
+    /// Whether the resource attribute was sent as a header, leaving it out of the body.
+    fn sent_as_header(&self, key: &Key, value: &Value) -> bool {
+        self.header_resource
+            .as_ref()
+            .is_some_and(|header_resource| header_resource.get(key) == Some(value))
+    }
+}
+
+/// Produces the headers merged over the static ones of every request.
+#[derive(Clone)]
+struct DynamicHeaders(Arc<dyn Fn() -> HeaderMap + Send + Sync>);
+
+impl Debug for DynamicHeaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DynamicHeaders")
+    }
+}
+
+/// Options controlling how a batch of messages is encoded into the ingest request and
+/// how failures to deliver it are reported.
+#[derive(Debug, Clone)]
+struct RequestOptions {
+    /// Maximum number of requests in flight at any one time.
+    max_connections: Option<usize>,
+    compression: Compression,
+    /// Bodies of at most this many bytes are sent uncompressed.
+    compression_threshold: usize,
+    error_handler: Option<ErrorHandler>,
+    diagnostics: Option<Diagnostics>,
+    /// How long shutdown waits for exports in flight.
+    shutdown_grace_period: Duration,
+    /// Batches beyond this many exports in flight are dropped.
+    max_buffered_batches: Option<usize>,
+    /// Capacity of the queue requests are sent from, if exports queue their requests.
+    send_queue: Option<usize>,
+    retries: Retries,
+    /// Interval the exporter's stats are logged at, if enabled.
+    health_logging: Option<Duration>,
+    mixed_services: MixedServices,
+    target_streams: Option<TargetStreams>,
+    stream_router: Option<StreamRouter>,
+    ingest_format: IngestFormat,
+    dynamic_headers: Option<DynamicHeaders>,
+    /// Requests are logged instead of sent.
+    dry_run: bool,
+    /// Dry run bodies are logged indented.
+    pretty_json: bool,
+    /// Records are collected here instead of sent.
+    sink: Option<Arc<Mutex<Vec<serde_json::Value>>>>,
+}
+
+/// How requests are retried when Parseable can't be reached, fails with a 5xx status or
+/// rate limits them with `429 Too Many Requests`.
+#[derive(Debug, Clone)]
+struct Retries {
+    /// Retries of requests that failed without a response or with a 5xx status.
+    max_failure_retries: u32,
+    /// Backoff before the first retry of a failed request, doubling with every retry.
+    initial_backoff: Duration,
+    max_rate_limit_retries: usize,
+    /// Total time a request may spend waiting for `Retry-After` delays.
+    max_wait: Duration,
+    /// Clock `Retry-After` dates are compared against and log events are stamped with.
+    clock: Arc<dyn Clock>,
+    jitter: JitterStrategy,
+}
+
+/// Backoff before the first retry of a rate limited request, doubling with every retry.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest backoff between retries of a failed request.
+const MAX_FAILURE_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Exports spans to Parseable.
+///
+/// Clones share the exporter's connection pool, send queue, stats and in-flight exports,
+/// so one exporter can be registered with several tracer providers, e.g. one per
+/// service in the process, with [`MixedServices::Split`] or
+/// [`with_target_streams`](ParseableExporterBuilder::with_target_streams) picking each
+/// span's stream. Shutting down any of the providers waits for the exports of all of
+/// them.
+#[derive(Debug, Clone)]
 pub struct ParseableExporter {
     client: Arc<reqwest::Client>,
+    /// Client retried with when a request over `client` fails without a response.
+    fallback_client: Option<Arc<reqwest::Client>>,
     request_url: Url,
     request_headers: HeaderMap,
+    connection_limit: Option<Arc<Semaphore>>,
+    message_options: MessageOptions,
+    request_options: RequestOptions,
+    schema_publisher: Option<Arc<SchemaPublisher>>,
+    in_flight: Arc<InFlight>,
+    send_queue: Option<Arc<SendQueue>>,
+    stats: Arc<ExportStats>,
+    /// Sets of services whose mixed batches were reported already.
+    mixed_services_reported: Arc<Mutex<HashSet<BTreeSet<String>>>>,
 }
 
 impl ParseableExporter {
     pub(crate) fn new(
         client: reqwest::Client,
+        fallback_client: Option<reqwest::Client>,
         request_url: Url,
         request_headers: HeaderMap,
+        message_options: MessageOptions,
+        request_options: RequestOptions,
+        schema_publisher: Option<SchemaPublisher>,
     ) -> Self {
         ParseableExporter {
             client: Arc::new(client),
+            fallback_client: fallback_client.map(Arc::new),
             request_url,
             request_headers,
+            connection_limit: request_options
+                .max_connections
+                .map(|n| Arc::new(Semaphore::new(n))),
+            message_options,
+            send_queue: request_options
+                .send_queue
+                .map(|capacity| Arc::new(SendQueue::new(capacity))),
+            stats: Arc::new(ExportStats::new(
+                request_options.retries.clock.clone(),
+                request_options.health_logging,
+            )),
+            request_options,
+            schema_publisher: schema_publisher.map(Arc::new),
+            in_flight: Arc::default(),
+            mixed_services_reported: Arc::default(),
+        }
+    }
+
+    /// Totals of the exports so far, shared by all clones of the exporter, e.g. to report
+    /// the exporter's health on an application's own metrics endpoint. To read them
+    /// once the exporter is registered with a provider, keep a clone of it.
+    pub fn stats(&self) -> ExporterStats {
+        self.stats.snapshot()
+    }
+
+    /// Flatten and send `spans` right away, bypassing any batch processor and its batch
+    /// configuration, and wait for Parseable's response. Unlike [`export`], the error
+    /// carries the status and body of an unsuccessful response, which makes this suited
+    /// for tests and tooling asserting on the round trip. Failures are also passed to
+    /// the error handler.
+    ///
+    /// [`export`]: export::trace::SpanExporter::export
+    pub async fn export_now(&self, spans: Vec<SpanData>) -> Result<(), ParseableExporterError> {
+        let exporter = self.clone();
+        runtime::on_tokio(async move { exporter.send_now(spans).await }).await
+    }
+
+    async fn send_now(&self, spans: Vec<SpanData>) -> Result<(), ParseableExporterError> {
+        let mut traces = into_trace_messages(spans, &self.message_options);
+        if traces.is_empty() {
+            return Ok(());
+        }
+        self.stats.start_health_logging(|| self.stream());
+        let _in_flight = self.start_export(traces.len()).map_err(|failure| {
+            self.stats.record(false, traces.len());
+            failure.report(
+                self.request_options.error_handler.as_ref(),
+                self.stream(),
+                traces.len(),
+            )
+        })?;
+        let request = self.build_request(&mut traces, None);
+        if let Some(schema_publisher) = &self.schema_publisher {
+            schema_publisher.ensure_published(&self.client).await;
+        }
+        let result = send_request(
+            self.client.clone(),
+            self.fallback_client.clone(),
+            request,
+            self.connection_limit.clone(),
+            self.request_options.diagnostics,
+            self.request_options.retries.clone(),
+            self.request_options.dry_run,
+        )
+        .await;
+        self.stats.record(result.is_ok(), traces.len());
+        result.map_err(|failure| {
+            failure.report(
+                self.request_options.error_handler.as_ref(),
+                self.stream(),
+                traces.len(),
+            )
+        })
+    }
+
+    /// Register an export of `message_count` records, failing when
+    /// [`with_max_buffered_batches`](ParseableExporterBuilder::with_max_buffered_batches)
+    /// batches are held already.
+    fn start_export(&self, message_count: usize) -> Result<InFlightGuard, SendFailure> {
+        let Some(limit) = self.request_options.max_buffered_batches else {
+            return Ok(self.in_flight.start());
+        };
+        self.in_flight.try_start(limit).ok_or_else(|| {
+            SendFailure::from(TraceError::from(format!(
+                "dropped a batch of {message_count} records, {limit} batches are buffered already"
+            )))
+        })
+    }
+
+    /// Queue `request` for the send queue's task, spawning the task on the current Tokio
+    /// runtime for the first request.
+    fn queue_request(
+        &self,
+        send_queue: &SendQueue,
+        request: Result<reqwest::Request, TraceError>,
+        message_count: usize,
+        stream: String,
+        in_flight: InFlightGuard,
+    ) -> BoxFuture<'static, export::trace::ExportResult> {
+        let Some(runtime) = runtime::handle() else {
+            return Box::pin(async {
+                Err(TraceError::from("no Tokio runtime to send the requests on"))
+            });
+        };
+        if let Some(receiver) = send_queue.take_receiver() {
+            runtime.spawn(self.drain_send_queue(receiver));
+        }
+        let sender = send_queue.sender();
+        let queued = QueuedRequest {
+            request,
+            message_count,
+            stream,
+            in_flight,
+        };
+        Box::pin(async move {
+            sender
+                .send(queued)
+                .await
+                .map_err(|_| TraceError::from("send queue is closed"))
+        })
+    }
+
+    /// Send the queued requests one at a time, until the exporter is dropped.
+    fn drain_send_queue(
+        &self,
+        mut receiver: tokio::sync::mpsc::Receiver<QueuedRequest>,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let client = self.client.clone();
+        let fallback_client = self.fallback_client.clone();
+        let connection_limit = self.connection_limit.clone();
+        let schema_publisher = self.schema_publisher.clone();
+        let error_handler = self.request_options.error_handler.clone();
+        let diagnostics = self.request_options.diagnostics;
+        let dry_run = self.request_options.dry_run;
+        let retries = self.request_options.retries.clone();
+        let stats = self.stats.clone();
+        async move {
+            while let Some(queued) = receiver.recv().await {
+                if let Some(schema_publisher) = &schema_publisher {
+                    schema_publisher.ensure_published(&client).await;
+                }
+                let sent = send_request(
+                    client.clone(),
+                    fallback_client.clone(),
+                    queued.request,
+                    connection_limit.clone(),
+                    diagnostics,
+                    retries.clone(),
+                    dry_run,
+                )
+                .await;
+                stats.record(sent.is_ok(), queued.message_count);
+                if let Err(failure) = sent {
+                    failure.report(error_handler.as_ref(), queued.stream, queued.message_count);
+                    global::handle_error(failure.error);
+                }
+                drop(queued.in_flight);
+            }
+        }
+    }
+
+    /// Name of the stream the exporter sends to.
+    fn stream(&self) -> String {
+        self.request_headers
+            .get("X-P-Stream")
+            .and_then(|stream| stream.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Serialize the messages into an ingest request, compressing the body when configured.
+    /// The request goes to `stream` instead of the exporter's stream, if given.
+    /// With the envelope format, the batch-wide fields are moved out of the messages.
+    fn build_request(
+        &self,
+        traces: &mut [TraceMessage],
+        stream: Option<&String>,
+    ) -> Result<reqwest::Request, TraceError> {
+        let body = if self.message_options.envelope {
+            let meta = EnvelopeMeta::hoist(traces);
+            serde_json::to_vec(&Envelope {
+                meta,
+                records: traces,
+            })
+        } else {
+            self.request_options.ingest_format.serialize(traces)
+        };
+        let body = body.map_err(|e| TraceError::Other(Box::new(e)))?;
+        self.request_with_body(body, stream)
+    }
+
+    /// Build the ingest request sending the serialized records `body` to `stream`, or
+    /// the exporter's stream.
+    fn request_with_body(
+        &self,
+        mut body: Vec<u8>,
+        stream: Option<&String>,
+    ) -> Result<reqwest::Request, TraceError> {
+        if let Some(sink) = &self.request_options.sink {
+            let mut sink = sink.lock().unwrap();
+            for value in serde_json::Deserializer::from_slice(&body).into_iter() {
+                match value.map_err(|e| TraceError::Other(Box::new(e)))? {
+                    serde_json::Value::Array(records) => sink.extend(records),
+                    record => sink.push(record),
+                }
+            }
+        } else if self.request_options.dry_run {
+            let logged = match self.request_options.pretty_json {
+                true => pretty_json(&body)?,
+                false => String::from_utf8_lossy(&body).into_owned(),
+            };
+            tracing::debug!(
+                stream = stream.cloned().unwrap_or_else(|| self.stream()),
+                body = %logged,
+                "Parseable dry run, not sending request"
+            );
+        }
+        let mut headers = self.request_headers.clone();
+        if let Some(DynamicHeaders(dynamic_headers)) = &self.request_options.dynamic_headers {
+            headers.extend(dynamic_headers());
+            if let Some(stream) = self.request_headers.get("X-P-Stream") {
+                headers.insert("X-P-Stream", stream.clone());
+            }
+        }
+        if let Some(stream) = stream {
+            headers.insert(
+                "X-P-Stream",
+                HeaderValue::from_str(stream).map_err(|e| TraceError::Other(Box::new(e)))?,
+            );
+        }
+        let mut request = self
+            .client
+            .request(Method::POST, self.request_url.clone())
+            .headers(headers);
+
+        let compression = self.request_options.compression;
+        if let Some(encoding) = compression.content_encoding() {
+            if body.len() > self.request_options.compression_threshold {
+                body = compression
+                    .compress(&body)
+                    .map_err(|e| TraceError::Other(Box::new(e)))?;
+                request = request.header(CONTENT_ENCODING, encoding);
+            }
         }
+
+        request
+            .body(body)
+            .build()
+            .map_err(|e| TraceError::Other(Box::new(e)))
     }
 }
 
+/// Version of Parseable's HTTP API the exporter ingests through, `V1` by default.
+///
+/// Both versions accept the same JSON array of records with the same headers, so the
+/// version only selects the path requests are sent to.
 #[derive(Debug)]
 pub enum ParseableApiVersion {
     V1,
+    V2,
 }
 
 impl ParseableApiVersion {
     pub fn path(&self) -> &'static str {
         match self {
             ParseableApiVersion::V1 => "api/v1",
+            ParseableApiVersion::V2 => "api/v2",
         }
     }
 
@@ -99,17 +851,134 @@ impl ParseableApiVersion {
     }
 }
 
+/// How the records of a batch are framed in the request body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IngestFormat {
+    /// A single JSON array of records.
+    #[default]
+    JsonArray,
+    /// Newline-delimited JSON, one record per line, sent as `application/x-ndjson`.
+    Ndjson,
+}
+
+impl IngestFormat {
+    fn content_type(&self, api_version: &ParseableApiVersion) -> &'static str {
+        match self {
+            IngestFormat::JsonArray => api_version.content_type(),
+            IngestFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    /// Serialize `records` into a request body of this format.
+    fn serialize<T: Serialize>(&self, records: &[T]) -> serde_json::Result<Vec<u8>> {
+        match self {
+            IngestFormat::JsonArray => serde_json::to_vec(records),
+            IngestFormat::Ndjson => {
+                let mut body = Vec::new();
+                for record in records {
+                    serde_json::to_writer(&mut body, record)?;
+                    body.push(b'\n');
+                }
+                Ok(body)
+            }
+        }
+    }
+}
+
 pub struct ParseableExporterBuilder {
     tls_enabled: bool,
     host: String,
     port: String,
+    /// Path Parseable's API is served under, e.g. behind a reverse proxy.
+    base_path: String,
+    endpoint: Option<Url>,
     api_version: ParseableApiVersion,
     username: String,
     password: String,
+    /// Credentials replacing the username and password, if set.
+    auth: Option<Auth>,
+    /// Timeout of the requests of the default client.
+    timeout: Duration,
+    /// PEM encoded client certificate chain and PKCS #8 key of the default client.
+    client_identity: Option<(Vec<u8>, Vec<u8>)>,
+    /// PEM encoded certificates the default client trusts in addition to the system ones.
+    root_cas: Vec<Vec<u8>>,
     service_name: String,
+    metrics_stream: Option<String>,
     client: Option<reqwest::Client>,
+    max_connections_per_host: Option<usize>,
+    /// Idle connections the default client keeps per host, if not capped by
+    /// `max_connections_per_host`.
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    /// Proxies of the default client, replacing those taken from the environment.
+    proxies: Vec<reqwest::Proxy>,
+    #[cfg(feature = "http3")]
+    http3: bool,
+    redirect_policy: Option<redirect::Policy>,
+    resource_as_headers: bool,
+    /// Resource the headers are taken from when no config provides one.
+    resource: Option<sdk::Resource>,
+    resource_attribute_keys: Option<HashSet<Key>>,
+    /// Attributes merged into the resource of the installed provider.
+    resource_attributes: Vec<KeyValue>,
+    exporter_id: bool,
+    stream_field: bool,
+    publish_schema: bool,
+    string_interning: bool,
+    sort_by_end_time: bool,
+    span_kinds: Option<Vec<SpanKind>>,
+    max_trace_depth: Option<usize>,
+    local_root: bool,
+    correlation_id: bool,
+    attribute_key_map: Option<HashMap<Key, Key>>,
+    attribute_allowlist: Option<HashSet<Key>>,
+    value_normalization: Option<NormalizeOptions>,
+    type_coercion: Option<HashMap<Key, AttributeType>>,
+    attribute_units: Option<HashMap<Key, String>>,
+    json_attribute_keys: Option<HashSet<Key>>,
+    max_array_elements: Option<usize>,
+    typed_attributes: bool,
+    namespaced_attributes: bool,
+    envelope_format: bool,
+    ingest_format: IngestFormat,
+    attribute_key_case: CaseMode,
+    /// Patterns of the attribute keys whose values are redacted.
+    redacted_keys: Vec<String>,
+    redaction_mode: RedactionMode,
+    duration_unit: DurationUnit,
+    timestamp_precision: TimestampPrecision,
+    null_handling: NullHandling,
+    numeric_status: bool,
+    compression: Compression,
+    compression_threshold: usize,
+    error_handler: Option<ErrorHandler>,
+    diagnostics: bool,
+    diagnostics_body_limit: usize,
+    shutdown_grace_period: Duration,
+    max_buffered_batches: Option<usize>,
+    send_queue: Option<usize>,
+    retries: Retries,
+    health_logging: Option<Duration>,
+    mixed_services: MixedServices,
+    target_streams: Option<TargetStreams>,
+    stream_router: Option<StreamRouter>,
+    startup_marker: bool,
+    batch_settings: Option<BatchSettings>,
+    /// Batch settings taking precedence over the environment.
+    max_queue_size: Option<usize>,
+    max_export_batch_size: Option<usize>,
+    scheduled_delay: Option<Duration>,
+    batch_export_timeout: Option<Duration>,
+    span_processors: Vec<BoxedProcessor>,
+    byte_flush: Option<usize>,
+    min_batch_size: Option<(usize, Duration)>,
     metadata: Option<http::HeaderMap>,
     tags: Option<http::HeaderMap>,
+    dynamic_headers: Option<DynamicHeaders>,
+    dry_run: bool,
+    pretty_json: bool,
+    sink: Option<Arc<Mutex<Vec<serde_json::Value>>>>,
 }
 
 impl ParseableExporterBuilder {
@@ -128,6 +997,24 @@ impl ParseableExporterBuilder {
         self
     }
 
+    /// Sets the path prefix Parseable's API is served under when a reverse proxy exposes
+    /// it at a sub-path, e.g. `telemetry` to send to `/telemetry/api/v1/ingest`. Leading
+    /// and trailing slashes are ignored; empty by default.
+    pub fn with_base_path<T: Into<String>>(mut self, base_path: T) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Sets the URL to send to, replacing the one built from the host, port, TLS setting
+    /// and base path, e.g. for IPv6 hosts or endpoints without an explicit port. The URL
+    /// is used verbatim when its path ends with `ingest`, as in
+    /// `https://traces.example.com/api/v1/ingest`; otherwise the API version's ingest
+    /// path is appended to it.
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
     pub fn with_api_version(mut self, api_version: ParseableApiVersion) -> Self {
         self.api_version = api_version;
         self
@@ -148,173 +1035,1911 @@ impl ParseableExporterBuilder {
         self
     }
 
-    pub fn with_client(mut self, client: reqwest::Client) -> Self {
-        self.client = Some(client);
+    /// Calls `headers` for every request and merges the headers it returns over the
+    /// static ones, including metadata and tags, e.g. for a request signature that
+    /// expires. Retries of a request reuse its headers; the `X-P-Stream` header routing a
+    /// batch always takes precedence.
+    pub fn with_dynamic_headers<F>(mut self, headers: F) -> Self
+    where
+        F: Fn() -> http::HeaderMap + Send + Sync + 'static,
+    {
+        self.dynamic_headers = Some(DynamicHeaders(Arc::new(headers)));
         self
     }
 
-    pub fn with_username<T: Into<String>>(mut self, username: T) -> Self {
-        self.username = username.into();
+    /// Logs every request body at debug level instead of sending it, and reports the
+    /// export as successful, to see what would be sent while developing locally or in
+    /// CI without a Parseable instance. No schema is published either.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
         self
     }
 
-    pub fn with_password<T: Into<String>>(mut self, password: T) -> Self {
-        self.password = password.into();
+    /// Logs the bodies of a [dry run](Self::with_dry_run) as indented JSON, to read them
+    /// without piping them through `jq`. Bodies sent to Parseable stay compact.
+    pub fn with_pretty_json(mut self) -> Self {
+        self.pretty_json = true;
         self
     }
 
-    pub fn install_batch<R: TraceRuntime>(
-        self,
-        runtime: R,
-        config: sdk::trace::Config,
-    ) -> Result<sdk::trace::Tracer, TraceError> {
-        let exporter = self.build_exporter()?;
-        let bz = BatchSpanProcessor::builder(exporter, runtime)
-            .with_batch_config(get_batch_config())
-            .build();
-        let provider_builder = sdk::trace::TracerProvider::builder()
-            .with_span_processor(bz)
-            .with_config(config);
-        let provider = provider_builder.build();
-        let tracer = provider.versioned_tracer(
-            "opentelemetry-parseable",
-            Some(env!("CARGO_PKG_VERSION")),
-            None,
-        );
-        let _ = global::set_tracer_provider(provider);
-        Ok(tracer)
+    /// Appends the records of every request to `sink` instead of sending them, e.g. to
+    /// assert on what would be exported in tests. Records are the JSON objects Parseable
+    /// would receive, or the whole batch with the
+    /// [envelope format](Self::with_envelope_format).
+    pub fn with_sink(mut self, sink: Arc<Mutex<Vec<serde_json::Value>>>) -> Self {
+        self.sink = Some(sink);
+        self
     }
 
-    pub fn install_simple<R: TraceRuntime>(
-        self,
-        config: sdk::trace::Config,
-    ) -> Result<sdk::trace::Tracer, TraceError> {
-        let exporter = self.build_exporter()?;
-        let provider_builder = sdk::trace::TracerProvider::builder()
-            .with_simple_exporter(exporter)
-            .with_config(config);
-        let provider = provider_builder.build();
-        let tracer = provider.versioned_tracer(
-            "opentelemetry-parseable",
-            Some(env!("CARGO_PKG_VERSION")),
-            None,
-        );
-        let _ = global::set_tracer_provider(provider);
-        Ok(tracer)
+    /// Sends the resource attributes once per request as `X-P-Meta-<key>` headers
+    /// instead of repeating them in every record's `resource_attributes`.
+    ///
+    /// Only attributes whose key consists of lowercase ASCII letters, digits, `.`, `_`
+    /// and `-`, and whose value is a valid header value without surrounding
+    /// whitespace, are mapped to headers; the rest stay in `resource_attributes`,
+    /// which is omitted entirely when every attribute was mapped. The resource is
+    /// taken from the `Config` passed to `install_batch`/`install_simple`, and otherwise
+    /// from [`with_resource`](Self::with_resource). An attribute is only left out of a
+    /// record when the record's resource has the same value as the header.
+    pub fn with_resource_as_headers(mut self) -> Self {
+        self.resource_as_headers = true;
+        self
     }
 
-    fn _build_endpoint(&self) -> Result<Url, TraceError> {
-        let http_protocol = if self.tls_enabled { "https" } else { "http" };
-        let url = format!(
-            "{}://{}:{}/{}/ingest",
-            http_protocol,
-            self.host,
-            self.port,
-            self.api_version.path()
-        );
-        url.parse::<Url>()
-            .map_err(|e| TraceError::Other(Box::new(e)))
+    /// Resource [`with_resource_as_headers`](Self::with_resource_as_headers) sends when
+    /// building with [`build_exporter`](Self::build_exporter),
+    /// [`build_log_layer`](Self::build_log_layer) or
+    /// [`build_metrics_exporter`](Self::build_metrics_exporter), i.e. the resource of the
+    /// provider the exporter is registered with. The SDK's default resource if unset;
+    /// `install_batch` and `install_simple` use the resource of their config instead.
+    pub fn with_resource(mut self, resource: sdk::Resource) -> Self {
+        self.resource = Some(resource);
+        self
     }
 
-    fn build_exporter(self) -> Result<ParseableExporter, TraceError> {
-        let endpoint = self._build_endpoint()?;
-        if let Some(client) = self.client {
-            // We add here the stream name, that will be the name of the service we are going to trace
-            let mut headers = HeaderMap::new();
-            let encoded_auth =
-                base64encoder::STANDARD.encode(format!("{}:{}", self.username, self.password));
-            headers.insert(
-                "Authorization",
-                HeaderValue::from_str(&format!("Basic {encoded_auth}"))
-                    .map_err(|e| TraceError::Other(Box::new(e)))?,
-            );
-            headers.insert(
-                "Content-Type",
-                HeaderValue::from_static(self.api_version.content_type()),
-            );
-            headers.insert(
-                "X-P-Stream",
-                HeaderValue::from_str(&self.service_name)
-                    .map_err(|e| TraceError::Other(Box::new(e)))?,
-            );
-
-            // Metadata
-            if let Some(metadata) = self.metadata {
-                headers.extend(metadata);
-            }
+    /// Writes only the listed resource attributes to each record's
+    /// `resource_attributes`, dropping the rest of the resource, e.g. to keep
+    /// `service.name` but not every host and build detail. All resource attributes are
+    /// written by default. The field is left out when none of the keys is present.
+    pub fn with_resource_attribute_keys(mut self, keys: Vec<String>) -> Self {
+        self.resource_attribute_keys = Some(keys.into_iter().map(Key::from).collect());
+        self
+    }
 
-            // Tags
-            if let Some(tags) = self.tags {
-                headers.extend(tags);
-            }
+    /// Adds static attributes, e.g. `deployment.environment`, to the resource of the
+    /// `Config` passed to `install_batch`/`install_simple`, so every record carries them
+    /// without building the resource by hand. They replace resource attributes of the
+    /// same key. Can be called repeatedly to add more.
+    pub fn with_resource_attributes<I: IntoIterator<Item = KeyValue>>(
+        mut self,
+        attributes: I,
+    ) -> Self {
+        self.resource_attributes.extend(attributes);
+        self
+    }
 
-            Ok(ParseableExporter::new(client, endpoint, headers))
-        } else {
-            Err(TraceError::from("No HttpClient provided"))
-        }
+    /// Tags every record with an `exporter_id`, a random UUID generated when the exporter
+    /// is built, to tell apart the instances of a fleet writing to the same stream.
+    /// Unlike the host resource attributes it is regenerated for every process, so a
+    /// restarted instance reports a new id.
+    pub fn with_exporter_id(mut self) -> Self {
+        self.exporter_id = true;
+        self
     }
-}
 
-impl Default for ParseableExporterBuilder {
-    fn default() -> Self {
-        ParseableExporterBuilder {
-            tls_enabled: false,
-            host: env::var("PARSEABLE_HOST").unwrap_or_else(|_| "0.0.0.0".into()),
-            port: env::var("PARSEABLE_PORT").unwrap_or_else(|_| "8000".into()),
-            api_version: ParseableApiVersion::V1,
-            username: env::var("PARSEABLE_USERNAME").unwrap_or_else(|_| "admin".into()),
-            password: env::var("PARSEABLE_PASSWORD").unwrap_or_else(|_| "admin".into()),
-            service_name: "my-service".into(),
-            client: Some(reqwest::Client::new()),
-            metadata: None,
-            tags: None,
-        }
+    /// Adds a `stream` field carrying the `X-P-Stream` the record is sent to, so records
+    /// keep their origin once data from several streams is combined, e.g. in an export
+    /// from Parseable to a data lake.
+    pub fn with_stream_field(mut self) -> Self {
+        self.stream_field = true;
+        self
     }
-}
 
-impl export::trace::SpanExporter for ParseableExporter {
-    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, export::trace::ExportResult> {
-        let traces = into_trace_messages(batch);
-        let req = self
-            .client
-            .request(Method::POST, self.request_url.clone())
-            .headers(self.request_headers.clone())
-            .json(&traces)
-            .build()
-            .map_err(|e| TraceError::Other(Box::new(e)));
+    /// Publishes an explicit schema for the stream before the first export, so its columns
+    /// and types don't depend on the content of the first record Parseable sees.
+    ///
+    /// This relies on Parseable's static schema support in the stream creation API. If
+    /// the server rejects the schema, e.g. because it predates static schemas or the
+    /// stream already exists, the error goes to the global OpenTelemetry error handler
+    /// and spans are still exported.
+    pub fn with_publish_schema(mut self) -> Self {
+        self.publish_schema = true;
+        self
+    }
 
+    /// Deduplicates identical attribute strings within a batch, so e.g. the resource
+    /// attributes repeated on every record share a single allocation. The serialized
+    /// records are unchanged; this only reduces memory churn for large batches.
+    pub fn with_string_interning(mut self) -> Self {
+        self.string_interning = true;
+        self
+    }
+
+    /// Sorts the records of each batch by span end time, rather than the order the SDK
+    /// handed the spans over in. Only records within a batch are ordered; batches may
+    /// still reach Parseable out of order.
+    pub fn with_sort_by_end_time(mut self) -> Self {
+        self.sort_by_end_time = true;
+        self
+    }
+
+    /// Exports only spans of the given kinds, e.g. [`SpanKind::Server`] and
+    /// [`SpanKind::Client`] to leave out the high-volume internal spans. Spans of every
+    /// kind are exported by default.
+    pub fn with_export_span_kinds(mut self, kinds: impl IntoIterator<Item = SpanKind>) -> Self {
+        self.span_kinds = Some(kinds.into_iter().collect());
+        self
+    }
+
+    /// Drops spans nested more than `depth` levels below the root of their trace, to cut
+    /// the volume of deeply recursive instrumentation; `0` keeps root spans only.
+    ///
+    /// A span's depth is only known from the ancestors exported in the same batch, and
+    /// parents usually end, and so are exported, after their children. A span is dropped
+    /// only once the ancestors in its batch, plus one for a parent outside the batch,
+    /// already put it below the limit, so a span may be kept even though its full trace
+    /// is deeper.
+    pub fn with_max_trace_depth(mut self, depth: usize) -> Self {
+        self.max_trace_depth = Some(depth);
+        self
+    }
+
+    /// Adds a `local_root` field that is `true` for the spans entering this service
+    /// within their trace, i.e. the root spans and those with a remote parent, to find
+    /// service entry points without guessing from span kinds.
+    ///
+    /// The SDK doesn't tell whether a parent is remote, so a span counts as a local root
+    /// when its parent isn't in the same batch. A local child whose parent is exported
+    /// in a later batch is flagged too.
+    pub fn with_local_root(mut self) -> Self {
+        self.local_root = true;
+        self
+    }
+
+    /// Renames attribute keys before serialization, e.g. `http.method` to `method` for
+    /// dashboards that predate the semantic conventions. Keys missing from the map are
+    /// kept as they are. Remapping happens after attributes are filtered, so resource
+    /// attributes sent as headers by [`with_resource_as_headers`](Self::with_resource_as_headers)
+    /// keep their original keys.
+    pub fn with_attribute_key_map(mut self, key_map: HashMap<String, String>) -> Self {
+        self.attribute_key_map = Some(
+            key_map
+                .into_iter()
+                .map(|(from, to)| (Key::from(from), Key::from(to)))
+                .collect(),
+        );
+        self
+    }
+
+    /// Exports only the span and event attributes whose keys are listed, dropping all
+    /// others. Keys are matched before
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renames them. Resource
+    /// attributes are not filtered.
+    pub fn with_attribute_allowlist(mut self, keys: HashSet<String>) -> Self {
+        self.attribute_allowlist = Some(keys.into_iter().map(Key::from).collect());
+        self
+    }
+
+    /// Case attribute keys are written in. [`CaseMode::Lower`] lowercases every key, after
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renamed it, so keys from
+    /// different instrumentation that only differ in case share a column. When that
+    /// makes attributes of a span collide, the one whose key was lowercase already is
+    /// kept. Keys are written as they are by default.
+    pub fn with_attribute_key_case(mut self, case: CaseMode) -> Self {
+        self.attribute_key_case = case;
+        self
+    }
+
+    /// Redacts the resource, span and event attributes whose keys are listed, e.g.
+    /// `user.email`, along with the fields of exported logs and the attributes of
+    /// metrics, according to [`with_redaction_mode`](Self::with_redaction_mode).
+    /// Keys are matched case-insensitively before
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renames them, and a
+    /// trailing `*` matches every key with that prefix, e.g. `http.request.header.*`.
+    /// Redacted resource attributes are never sent as headers.
+    pub fn with_redacted_keys(mut self, keys: Vec<String>) -> Self {
+        self.redacted_keys = keys;
+        self
+    }
+
+    /// Whether [redacted](Self::with_redacted_keys) attributes are dropped, masked as
+    /// `***` (default) or replaced with the SHA-256 hash of their value.
+    pub fn with_redaction_mode(mut self, mode: RedactionMode) -> Self {
+        self.redaction_mode = mode;
+        self
+    }
+
+    /// Normalizes string attribute values, e.g. trimming `GET ` and lowercasing the
+    /// `http.method` values so they aggregate together in Parseable. Lowercasing only
+    /// applies to the keys configured in `options`, matched before
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renames them.
+    pub fn with_value_normalization(mut self, options: NormalizeOptions) -> Self {
+        self.value_normalization = Some(options);
+        self
+    }
+
+    /// Writes `attributes` and `resource_attributes` as JSON objects of typed values,
+    /// e.g. `{"http.status_code": 200}`, instead of lists of `key=value` strings, so
+    /// Parseable infers a column of the right type per attribute. Their columns depend on
+    /// the attributes seen, so [`with_publish_schema`](Self::with_publish_schema) leaves
+    /// them out of the published schema.
+    pub fn with_typed_attributes(mut self) -> Self {
+        self.typed_attributes = true;
+        self
+    }
+
+    /// Writes the resource, span and event attributes of a record as a single
+    /// `attributes` object with `resource`, `span` and `event` objects of typed values,
+    /// e.g. `{"resource": {"service.name": "api"}, "span": {"http.status_code": 200},
+    /// "event": {}}`, instead of the separate `resource_attributes`, `attributes` and
+    /// `event_attributes` fields. Implies [`with_typed_attributes`](Self::with_typed_attributes).
+    pub fn with_namespaced_attributes(mut self) -> Self {
+        self.namespaced_attributes = true;
+        self
+    }
+
+    /// Sends each batch as one JSON object, `{"meta": {...}, "records": [...]}`, whose
+    /// `meta` holds what is the same for every record of the batch: the `exporter_id`
+    /// and `stream` fields when enabled, and `resource_attributes` when all records share
+    /// them. Records leave these out, which shrinks large batches of a single resource
+    /// considerably.
+    ///
+    /// Parseable ingests arrays of records and doesn't unpack the envelope itself, so
+    /// this needs a pre-processor in front of Parseable that merges `meta` into each
+    /// record before ingestion.
+    pub fn with_envelope_format(mut self) -> Self {
+        self.envelope_format = true;
+        self
+    }
+
+    /// Sends batches as newline-delimited JSON with [`IngestFormat::Ndjson`], for ingest
+    /// setups expecting one record per line, instead of a JSON array. The
+    /// [envelope format](Self::with_envelope_format) is always a single JSON object.
+    pub fn with_ingest_format(mut self, format: IngestFormat) -> Self {
+        self.ingest_format = format;
+        self
+    }
+
+    /// Coerces the values of the given attribute keys to a type, e.g. `http.status_code`
+    /// to [`AttributeType::Int`] when some instrumentation sends it as the string
+    /// `"200"`, so the column type stays consistent. Values that can't be converted,
+    /// such as `"n/a"` for an int, are kept as they are. Keys are matched before
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renames them, and after
+    /// [`with_value_normalization`](Self::with_value_normalization) is applied.
+    pub fn with_type_coercion(mut self, rules: HashMap<String, AttributeType>) -> Self {
+        self.type_coercion = Some(
+            rules
+                .into_iter()
+                .map(|(key, attribute_type)| (Key::from(key), attribute_type))
+                .collect(),
+        );
+        self
+    }
+
+    /// Annotates the values of the given attribute keys with a unit, e.g. `ms` for
+    /// `db.duration`, written as a companion `<key>_unit` attribute (`db.duration_unit=ms`)
+    /// so dashboards can label and aggregate the values. Keys are matched before
+    /// [`with_attribute_key_map`](Self::with_attribute_key_map) renames them, and the
+    /// companion follows the renamed key. No attribute is annotated by default.
+    pub fn with_attribute_units(mut self, units: HashMap<String, String>) -> Self {
+        self.attribute_units = Some(
+            units
+                .into_iter()
+                .map(|(key, unit)| (Key::from(key), unit))
+                .collect(),
+        );
+        self
+    }
+
+    /// Parses the string values of the given attribute keys as JSON, for instrumentation
+    /// that records a whole object in one attribute, so its fields become queryable.
+    /// With [`with_typed_attributes`](Self::with_typed_attributes) an object or array
+    /// is embedded as is, e.g. `{"payload": {"user": {"id": 7}}}`; otherwise an object
+    /// is flattened into one `key=value` string per field, e.g. `payload.user.id=7`.
+    /// Values that aren't a JSON object or array are kept as strings. Keys are matched
+    /// before [`with_attribute_key_map`](Self::with_attribute_key_map) renames them.
+    pub fn with_json_attribute_parsing<K: Into<Key>>(
+        mut self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Self {
+        self.json_attribute_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Writes the span `status_code` as the OpenTelemetry status code (0 unset, 1 ok,
+    /// 2 error) instead of its name (`UNSET`, `OK`, `ERROR`), for tooling that expects the
+    /// OTLP representation.
+    pub fn with_numeric_status(mut self) -> Self {
+        self.numeric_status = true;
+        self
+    }
+
+    /// Truncates array attribute values to their first `max_elements` elements. A
+    /// truncated attribute is flagged by an extra `<key>.truncated` attribute holding
+    /// the number of elements dropped.
+    pub fn with_max_array_elements(mut self, max_elements: usize) -> Self {
+        self.max_array_elements = Some(max_elements);
+        self
+    }
+
+    /// Unit of the integer duration field, `duration_ms` (default), `duration_us` or
+    /// `duration_ns`. Durations are always integers so the column type Parseable infers
+    /// stays the same across batches.
+    pub fn with_duration_unit(mut self, unit: DurationUnit) -> Self {
+        self.duration_unit = unit;
+        self
+    }
+
+    /// Fractional digits of the RFC 3339 `start_time`, `end_time` and `event_timestamp`,
+    /// and of the log and metric timestamps, milliseconds by default. Finer precision keeps the start and end of sub-millisecond
+    /// spans apart.
+    pub fn with_timestamp_precision(mut self, precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = precision;
+        self
+    }
+
+    /// Calls `handler` once for every batch that fails to export, with the error, the
+    /// stream and the number of records lost. The same error is still returned to the span
+    /// processor, which reports it through the global OpenTelemetry error handler; this
+    /// callback only adds context for routing failures to alerting. It is never called
+    /// for successful exports.
+    pub fn with_error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ParseableExporterError) + Send + Sync + 'static,
+    {
+        self.error_handler = Some(ErrorHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Whether optional fields without a value, i.e. `event_message` and `event_timestamp`
+    /// on spans without events and `resource_attributes` once every resource attribute is
+    /// sent as a header, are omitted (default) or written as `null`. Explicit nulls keep
+    /// the set of columns Parseable infers stable across batches. Attribute values are
+    /// always written, including `false` booleans.
+    pub fn with_null_handling(mut self, null_handling: NullHandling) -> Self {
+        self.null_handling = null_handling;
+        self
+    }
+
+    /// Logs every request Parseable rejects at error level through `tracing`: the
+    /// request headers with credentials masked, the start of the serialized body (see
+    /// [`with_diagnostics_body_limit`](Self::with_diagnostics_body_limit)) and the response
+    /// status and body. Also enabled by setting `OTLP_DEBUG=1`. Off by default, as the
+    /// logs are noisy.
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics = true;
+        self
+    }
+
+    /// Request bodies logged by [`with_diagnostics`](Self::with_diagnostics) are
+    /// truncated to `bytes`, 2048 by default.
+    pub fn with_diagnostics_body_limit(mut self, bytes: usize) -> Self {
+        self.diagnostics_body_limit = bytes;
+        self
+    }
+
+    /// How long shutting the exporter down waits for requests still in flight to
+    /// complete, 5 seconds by default. Shutdown returns as soon as they are done.
+    pub fn with_shutdown_grace_period(mut self, grace_period: Duration) -> Self {
+        self.shutdown_grace_period = grace_period;
+        self
+    }
+
+    /// Caps the number of batches held by the exporter at once, whether their request is
+    /// in flight or waiting for a connection under
+    /// [`with_max_connections_per_host`](Self::with_max_connections_per_host). While
+    /// Parseable is slow, a batch arriving at the cap is dropped and reported as an
+    /// export error instead of being buffered. Spans still queued in the batch processor
+    /// are bounded separately by its queue size, so the spans held in memory are at most
+    /// roughly `OTLP_QUEUE_SIZE + max_batches * OTLP_BATCH_SIZE`.
+    pub fn with_max_buffered_batches(mut self, max_batches: usize) -> Self {
+        self.max_buffered_batches = Some(max_batches);
+        self
+    }
+
+    /// Queues the requests of exported batches for a background task to send, so an export
+    /// completes as soon as its batch is serialized and queued. Bursts of batches then
+    /// don't hold up the batch processor while Parseable is slow, at the cost of up to
+    /// `capacity` serialized batches held in memory, and export results no longer
+    /// reflecting delivery: failures are reported through the error handler and the
+    /// global OpenTelemetry error handler instead. Queued requests are sent one at a
+    /// time in the order they were exported. When the queue is full, exports wait for
+    /// room, applying back-pressure to the batch processor.
+    ///
+    /// The sending task is spawned on the Tokio runtime the first export runs on.
+    pub fn with_send_queue(mut self, capacity: usize) -> Self {
+        self.send_queue = Some(capacity);
+        self
+    }
+
+    /// How a request Parseable rate limits with `429 Too Many Requests` is retried. The
+    /// request is sent again after the delay the response's `Retry-After` header asks
+    /// for, if any, plus a backoff randomized by the
+    /// [retry jitter](Self::with_retry_jitter), up to `max_retries` times and as long as
+    /// the delays add up to at most `max_wait`, after which the 429 is the export's result.
+    /// Defaults to 3 retries within 10 seconds; `max_retries` of 0 disables retrying.
+    /// Keep `max_wait` below the batch processor's export timeout, which otherwise
+    /// abandons the export.
+    pub fn with_rate_limit_retries(mut self, max_retries: usize, max_wait: Duration) -> Self {
+        self.retries.max_rate_limit_retries = max_retries;
+        self.retries.max_wait = max_wait;
+        self
+    }
+
+    /// Retry requests that fail without a response, e.g. because the connection is
+    /// refused, or with a 5xx status up to `max_retries` times, waiting `initial_backoff`
+    /// before the first retry and twice as long before every further one, randomized by
+    /// the [retry jitter](Self::with_retry_jitter). Other 4xx statuses, like a 400 for a
+    /// malformed batch or a 401 for wrong credentials, fail right away. Defaults to 3
+    /// retries starting at 200ms; `max_retries` of 0 disables retrying.
+    pub fn with_retry(mut self, max_retries: u32, initial_backoff: Duration) -> Self {
+        self.retries.max_failure_retries = max_retries;
+        self.retries.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// How the backoff between retries of failed and rate limited requests is
+    /// randomized, so a fleet of exporters failing against a recovering Parseable spreads
+    /// out its retries. The backoff of rate limited requests starts from 500ms, that of
+    /// [failed ones](Self::with_retry) from the configured initial backoff, doubling with
+    /// every retry. Defaults to [`JitterStrategy::Full`].
+    pub fn with_retry_jitter(mut self, strategy: JitterStrategy) -> Self {
+        self.retries.jitter = strategy;
+        self
+    }
+
+    /// Log the exporter's stats at info level every `interval`: the batches and records
+    /// exported and failed so far and the time of the last successful export, to tell a
+    /// quiet service from one whose exports stopped reaching Parseable. The overhead is a
+    /// few atomic counter updates per export plus one timer and log line per interval.
+    ///
+    /// The logging task is spawned on the Tokio runtime the first export runs on and
+    /// stops once the exporter is dropped.
+    pub fn with_health_logging(mut self, interval: Duration) -> Self {
+        self.health_logging = Some(interval);
+        self
+    }
+
+    /// What to do with batches holding spans of several `service.name` resources, which
+    /// a tracer provider shared between services hands over: by default they go to the
+    /// one stream and a warning is logged, [`MixedServices::Split`] sends every span to
+    /// the stream named after its service instead. Only the schema of the exporter's own
+    /// stream is published by [`with_publish_schema`](Self::with_publish_schema).
+    pub fn with_mixed_services(mut self, handling: MixedServices) -> Self {
+        self.mixed_services = handling;
+        self
+    }
+
+    /// Routes spans to streams by the `tracing` target they were created under, e.g.
+    /// `myapp::db` to a `db-traces` stream, taking the stream of the longest matching
+    /// target. Targets match whole module path segments, so `myapp::db` also covers
+    /// `myapp::db::pool`. Other spans go to the exporter's stream, or their service's
+    /// with [`MixedServices::Split`].
+    ///
+    /// The target is read from the `code.namespace` attribute tracing-opentelemetry adds
+    /// from the span's module path, which is the target unless a span sets `target:`
+    /// explicitly. It is only recorded while the layer's `with_location` is enabled, as
+    /// it is by default.
+    pub fn with_target_streams(mut self, streams: HashMap<String, String>) -> Self {
+        self.target_streams = Some(TargetStreams::new(streams));
+        self
+    }
+
+    /// Routes spans to the stream `router` returns for them, e.g. the value of a
+    /// `stream.override` attribute, ahead of
+    /// [`with_target_streams`](Self::with_target_streams) and
+    /// [`MixedServices::Split`]. Spans it returns `None` for go to the stream those pick,
+    /// or the exporter's. A batch is sent as one request per stream.
+    pub fn with_stream_router<F>(mut self, router: F) -> Self
+    where
+        F: Fn(&SpanData) -> Option<String> + Send + Sync + 'static,
+    {
+        self.stream_router = Some(StreamRouter(Arc::new(router)));
+        self
+    }
+
+    /// Clock the exporter reads the current time from, the system clock by default.
+    /// Mostly useful to make time-dependent behavior deterministic in tests, e.g. with a
+    /// [`FixedClock`].
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.retries.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Request bodies of at most `bytes` are sent uncompressed even when compression is
+    /// enabled, as compressing tiny batches costs more CPU than it saves bandwidth.
+    /// Defaults to 1KB.
+    pub fn with_compression_threshold(mut self, bytes: usize) -> Self {
+        self.compression_threshold = bytes;
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Caps the number of requests in flight to Parseable at any one time.
+    ///
+    /// reqwest has no hard per-host connection limit, so the cap is enforced by the
+    /// exporter itself and applies to every export. For the auto-built client the idle
+    /// keep-alive pool is capped to the same size, so at most `max_connections`
+    /// connections are ever kept open to the host; a client passed via `with_client`
+    /// keeps its own pool settings.
+    pub fn with_max_connections_per_host(mut self, max_connections: usize) -> Self {
+        self.max_connections_per_host = Some(max_connections);
+        self
+    }
+
+    /// Keeps up to `max_idle` idle keep-alive connections per host in the default
+    /// client's pool, replacing the cap set by
+    /// [`with_max_connections_per_host`](Self::with_max_connections_per_host). reqwest
+    /// keeps any number by default.
+    pub fn with_pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// Closes connections of the default client that were idle for `timeout`, 90 seconds
+    /// by default. A longer timeout saves handshakes when batches are sent less often.
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sends the default client's requests through `proxy`. Without one, the client uses
+    /// the proxies configured by the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+    /// environment variables, which are ignored once a proxy is given. Can be called
+    /// repeatedly, the first proxy matching a request is used. Has no effect on a client
+    /// given to [`with_client`](Self::with_client).
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Sends requests over HTTP/3 (QUIC), which copes better with high-latency or lossy
+    /// networks. When a request can't be completed over HTTP/3, e.g. because the UDP port
+    /// is blocked or Parseable doesn't speak it, it is retried once over HTTP/1.1 or
+    /// HTTP/2. Only applies to the auto-built client.
+    ///
+    /// Experimental: requires the `http3` feature and building with
+    /// `RUSTFLAGS="--cfg reqwest_unstable"`, as reqwest's HTTP/3 support is unstable.
+    #[cfg(feature = "http3")]
+    pub fn with_http3(mut self) -> Self {
+        self.http3 = true;
+        self
+    }
+
+    /// Redirect policy of the auto-built client. Defaults to `Policy::none()`: a proxy
+    /// or load balancer redirecting e.g. HTTP to HTTPS may drop the `Authorization`
+    /// header along the way, so redirects are reported as export errors instead of
+    /// being followed into silent 401s.
+    pub fn with_redirect_policy(mut self, policy: redirect::Policy) -> Self {
+        self.redirect_policy = Some(policy);
+        self
+    }
+
+    /// Sets the username of [`Auth::Basic`], switching back to it from any other
+    /// [`with_auth`](Self::with_auth) credentials.
+    pub fn with_username<T: Into<String>>(mut self, username: T) -> Self {
+        self.username = username.into();
+        self.auth = None;
+        self
+    }
+
+    /// Sets the password of [`Auth::Basic`], switching back to it from any other
+    /// [`with_auth`](Self::with_auth) credentials.
+    pub fn with_password<T: Into<String>>(mut self, password: T) -> Self {
+        self.password = password.into();
+        self.auth = None;
+        self
+    }
+
+    /// Fails a request to Parseable that hasn't completed within `timeout`, from
+    /// connecting until the response body is read, so a hanging Parseable can't stall
+    /// the batch processor. Defaults to 30 seconds. Has no effect on a client given to
+    /// [`with_client`](Self::with_client), which keeps its own timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Authenticate to Parseable with a client certificate, for deployments behind a
+    /// proxy requiring mutual TLS. `cert_pem` holds the PEM encoded certificate chain and
+    /// `key_pem` the PEM encoded PKCS #8 private key. Has no effect on a client given to
+    /// [`with_client`](Self::with_client).
+    pub fn with_client_identity(mut self, cert_pem: Vec<u8>, key_pem: Vec<u8>) -> Self {
+        self.client_identity = Some((cert_pem, key_pem));
+        self
+    }
+
+    /// Trust the PEM encoded CA certificate `pem` in addition to the system's, e.g. for a
+    /// Parseable served with a certificate of a private CA. Can be called repeatedly to
+    /// add several certificates. Has no effect on a client given to
+    /// [`with_client`](Self::with_client).
+    pub fn with_root_ca(mut self, pem: Vec<u8>) -> Self {
+        self.root_cas.push(pem);
+        self
+    }
+
+    /// Sets the credentials requests are authorized with. Defaults to [`Auth::Basic`]
+    /// with the `PARSEABLE_USERNAME` and `PARSEABLE_PASSWORD` environment variables, or
+    /// `admin`/`admin`, Parseable's out-of-the-box credentials, which log a warning
+    /// when the exporter is built as they are rarely meant for production.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        match auth {
+            Auth::Basic { username, password } => {
+                self.username = username;
+                self.password = password;
+                self.auth = None;
+            }
+            auth => self.auth = Some(auth),
+        }
+        self
+    }
+
+    /// Registers a span processor ahead of the one exporting to Parseable, e.g. to enrich
+    /// or filter spans without forking the exporter. Processors are called in the order
+    /// they are added, and the exporting processor installed by
+    /// [`install_batch`](Self::install_batch) or [`install_simple`](Self::install_simple)
+    /// comes last. Only changes made in `on_start` are seen by the processors after it;
+    /// `on_end` hands each processor its own copy of the finished span.
+    pub fn with_span_processor<P: SpanProcessor + 'static>(mut self, processor: P) -> Self {
+        self.span_processors
+            .push(BoxedProcessor(Box::new(processor)));
+        self
+    }
+
+    /// Writes the value of the baggage entry `key` of the context each span is started
+    /// in to a top-level `correlation_id` field of its records, for joining traces to
+    /// business events on an indexed column. Baggage isn't part of finished spans, so a
+    /// [span processor](Self::with_span_processor) copies the value onto the span when
+    /// it starts; that processor is only registered by
+    /// [`install_batch`](Self::install_batch) and [`install_simple`](Self::install_simple).
+    pub fn with_correlation_id_from_baggage<K: Into<Key>>(self, key: K) -> Self {
+        let mut builder = self.with_span_processor(CorrelationIdProcessor(key.into()));
+        builder.correlation_id = true;
+        builder
+    }
+
+    /// Records a `parseable exporter started` span when the exporter is installed, so
+    /// restarts and configuration changes can be lined up with the traces in Parseable.
+    /// Its attributes carry the crate version, the sampler, the batch settings and a
+    /// fingerprint of them that changes whenever the configuration does.
+    pub fn with_startup_marker(mut self) -> Self {
+        self.startup_marker = true;
+        self
+    }
+
+    /// Batch settings used by [`install_batch`](Self::install_batch) instead of the
+    /// defaults. `OTLP_*` environment variables still take precedence over them.
+    pub fn with_batch_settings(mut self, settings: BatchSettings) -> Self {
+        self.batch_settings = Some(settings);
+        self
+    }
+
+    /// Maximum number of spans queued for batching by
+    /// [`install_batch`](Self::install_batch), taking precedence over `OTLP_QUEUE_SIZE` and
+    /// [`with_batch_settings`](Self::with_batch_settings).
+    pub fn with_max_queue_size(mut self, size: usize) -> Self {
+        self.max_queue_size = Some(size);
+        self
+    }
+
+    /// Maximum number of spans exported in one batch by
+    /// [`install_batch`](Self::install_batch), taking precedence over `OTLP_BATCH_SIZE` and
+    /// [`with_batch_settings`](Self::with_batch_settings).
+    pub fn with_max_export_batch_size(mut self, size: usize) -> Self {
+        self.max_export_batch_size = Some(size);
+        self
+    }
+
+    /// Delay between the scheduled exports of
+    /// [`install_batch`](Self::install_batch), taking precedence over `OTLP_INTERVAL_MILLIS` and
+    /// [`with_batch_settings`](Self::with_batch_settings).
+    pub fn with_scheduled_delay(mut self, delay: Duration) -> Self {
+        self.scheduled_delay = Some(delay);
+        self
+    }
+
+    /// How long the batch processor of [`install_batch`](Self::install_batch) waits for
+    /// an export before failing it with a timeout and moving on to the next batch, 30
+    /// seconds by default. Keep the [request timeout](Self::with_timeout), and the
+    /// [`with_rate_limit_retries`](Self::with_rate_limit_retries) budget, below this one.
+    /// An export abandoned after its timeout keeps its request going, and shutdown waits
+    /// for it up to the [shutdown grace period](Self::with_shutdown_grace_period). Not
+    /// applied with [`with_byte_flush`](Self::with_byte_flush) or
+    /// [`with_min_batch_size`](Self::with_min_batch_size), whose processor waits for every
+    /// export.
+    pub fn with_batch_export_timeout(mut self, timeout: Duration) -> Self {
+        self.batch_export_timeout = Some(timeout);
+        self
+    }
+
+    /// Also exports a batch as soon as the estimated size of its records reaches
+    /// `max_bytes`, on top of the count and time triggers of the batch settings. Only
+    /// applies to [`install_batch`](Self::install_batch), which then installs a
+    /// [`ByteBoundedProcessor`] instead of the SDK's batch processor.
+    pub fn with_byte_flush(mut self, max_bytes: usize) -> Self {
+        self.byte_flush = Some(max_bytes);
+        self
+    }
+
+    /// Holds spans until at least `min_spans` are buffered before the scheduled export
+    /// sends them, unless the oldest has waited for `max_age`, trading latency for fewer
+    /// requests on chatty, low value streams. Spans are then delayed by up to `max_age`
+    /// plus one scheduled delay of the batch settings; flushes and shutdown still export
+    /// whatever is buffered. Like [`with_byte_flush`](Self::with_byte_flush) this installs
+    /// a [`ByteBoundedProcessor`] in [`install_batch`](Self::install_batch).
+    pub fn with_min_batch_size(mut self, min_spans: usize, max_age: Duration) -> Self {
+        self.min_batch_size = Some((min_spans, max_age));
+        self
+    }
+
+    pub fn install_batch<R: TraceRuntime>(
+        mut self,
+        runtime: R,
+        config: sdk::trace::Config,
+    ) -> Result<sdk::trace::Tracer, TraceError> {
+        let config = self.merge_resource_attributes(config);
+        let byte_flush = self.byte_flush;
+        let min_batch_size = self.min_batch_size;
+        let batch_export_timeout = self.batch_export_timeout;
+        let span_processors = std::mem::take(&mut self.span_processors);
+        let settings = self
+            .batch_settings
+            .unwrap_or_default()
+            .overridden_by_env(Signal::Traces);
+        let settings = BatchSettings {
+            max_queue_size: self.max_queue_size.unwrap_or(settings.max_queue_size),
+            max_export_batch_size: self
+                .max_export_batch_size
+                .unwrap_or(settings.max_export_batch_size),
+            scheduled_delay: self.scheduled_delay.unwrap_or(settings.scheduled_delay),
+        };
+        let startup_marker = self.startup_marker.then(|| {
+            startup::marker_attributes(&config, Some(settings), byte_flush, self.compression)
+        });
+        let exporter = self.build_exporter_for(Some(&config.resource))?;
+        let provider_builder = span_processors.into_iter().fold(
+            sdk::trace::TracerProvider::builder(),
+            |builder, processor| builder.with_span_processor(processor),
+        );
+        let provider_builder = match (byte_flush, min_batch_size) {
+            (None, None) => {
+                let mut batch_config = BatchConfig::from(settings);
+                if let Some(timeout) = batch_export_timeout {
+                    batch_config = batch_config.with_max_export_timeout(timeout);
+                }
+                provider_builder.with_span_processor(
+                    BatchSpanProcessor::builder(exporter, runtime)
+                        .with_batch_config(batch_config)
+                        .build(),
+                )
+            }
+            (max_bytes, min_batch) => {
+                provider_builder.with_span_processor(ByteBoundedProcessor::spawn(
+                    exporter,
+                    runtime,
+                    settings,
+                    max_bytes.unwrap_or(usize::MAX),
+                    min_batch,
+                ))
+            }
+        };
+        let provider_builder = provider_builder.with_config(config);
+        let provider = provider_builder.build();
+        let tracer = provider.versioned_tracer(
+            "opentelemetry-parseable",
+            Some(env!("CARGO_PKG_VERSION")),
+            None,
+        );
+        let _ = global::set_tracer_provider(provider);
+        *INSTALLED_TRACER.lock().unwrap() = Some(tracer.clone());
+        if let Some(attributes) = startup_marker {
+            startup::emit_marker(&tracer, attributes);
+        }
+        Ok(tracer)
+    }
+
+    pub fn install_simple<R: TraceRuntime>(
+        mut self,
+        config: sdk::trace::Config,
+    ) -> Result<sdk::trace::Tracer, TraceError> {
+        let config = self.merge_resource_attributes(config);
+        let span_processors = std::mem::take(&mut self.span_processors);
+        let startup_marker = self
+            .startup_marker
+            .then(|| startup::marker_attributes(&config, None, None, self.compression));
+        let exporter = self.build_exporter_for(Some(&config.resource))?;
+        let provider_builder = span_processors
+            .into_iter()
+            .fold(
+                sdk::trace::TracerProvider::builder(),
+                |builder, processor| builder.with_span_processor(processor),
+            )
+            .with_simple_exporter(exporter)
+            .with_config(config);
+        let provider = provider_builder.build();
+        let tracer = provider.versioned_tracer(
+            "opentelemetry-parseable",
+            Some(env!("CARGO_PKG_VERSION")),
+            None,
+        );
+        let _ = global::set_tracer_provider(provider);
+        *INSTALLED_TRACER.lock().unwrap() = Some(tracer.clone());
+        if let Some(attributes) = startup_marker {
+            startup::emit_marker(&tracer, attributes);
+        }
+        Ok(tracer)
+    }
+
+    fn merge_resource_attributes(&mut self, mut config: sdk::trace::Config) -> sdk::trace::Config {
+        let attributes = std::mem::take(&mut self.resource_attributes);
+        if !attributes.is_empty() {
+            config.resource = Cow::Owned(config.resource.merge(&sdk::Resource::new(attributes)));
+        }
+        config
+    }
+
+    /// Check the settings that would otherwise fail obscurely, or only once exporting,
+    /// naming the offending one.
+    fn validate(&self) -> Result<(), TraceError> {
+        if self.endpoint.is_none() && self.host.trim().is_empty() {
+            return Err(TraceError::from("host must not be empty"));
+        }
+        if self.endpoint.is_none() && self.port.parse::<u16>().is_err() {
+            return Err(TraceError::from(format!(
+                "port must be a number between 0 and 65535, got '{}'",
+                self.port
+            )));
+        }
+        if self.service_name.is_empty() {
+            return Err(TraceError::from("service name must not be empty"));
+        }
+        if self.max_connections_per_host == Some(0) {
+            return Err(TraceError::from(
+                "max_connections_per_host must be greater than zero",
+            ));
+        }
+        if self.send_queue == Some(0) {
+            return Err(TraceError::from(
+                "send queue capacity must be greater than zero",
+            ));
+        }
+        if self.health_logging == Some(Duration::ZERO) {
+            return Err(TraceError::from(
+                "health logging interval must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+
+    fn _build_endpoint(&self) -> Result<Url, TraceError> {
+        let ingest_path = format!("{}/ingest", self.api_version.path());
+        if let Some(endpoint) = &self.endpoint {
+            if endpoint.path().trim_end_matches('/').ends_with("/ingest") {
+                return Ok(endpoint.clone());
+            }
+            let mut directory = endpoint.clone();
+            if !directory.path().ends_with('/') {
+                directory.set_path(&format!("{}/", directory.path()));
+            }
+            return directory.join(&ingest_path).map_err(|e| {
+                TraceError::from(format!("invalid Parseable endpoint '{endpoint}': {e}"))
+            });
+        }
+        let http_protocol = if self.tls_enabled { "https" } else { "http" };
+        let root = format!("{}://{}:{}/", http_protocol, self.host, self.port);
+        let base_path = self.base_path.trim_matches('/');
+        let url = root.parse::<Url>().and_then(|url| match base_path {
+            "" => url.join(&ingest_path),
+            base_path => url.join(&format!("{base_path}/"))?.join(&ingest_path),
+        });
+        url.map_err(|e| {
+            TraceError::from(format!(
+                "invalid Parseable endpoint '{root}{base_path}': {e}"
+            ))
+        })
+    }
+
+    /// Build the client for ingest requests, along with the client to fall back to when
+    /// it is unable to reach Parseable.
+    fn build_clients(&mut self) -> Result<(reqwest::Client, Option<reqwest::Client>), TraceError> {
+        let redirect_policy = self
+            .redirect_policy
+            .take()
+            .unwrap_or_else(redirect::Policy::none);
+        let max_idle = self
+            .pool_max_idle_per_host
+            .or(self.max_connections_per_host);
+        let idle_timeout = self.pool_idle_timeout;
+        let proxies = &self.proxies;
+        let timeout = self.timeout;
+        let identity = self
+            .client_identity
+            .as_ref()
+            .map(|(cert, key)| reqwest::Identity::from_pkcs8_pem(cert, key))
+            .transpose()
+            .map_err(|e| TraceError::from(format!("invalid client identity: {e}")))?;
+        let root_cas = self
+            .root_cas
+            .iter()
+            .map(|pem| reqwest::Certificate::from_pem(pem))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TraceError::from(format!("invalid root CA certificate: {e}")))?;
+        let builder = |redirect_policy| {
+            let mut builder = reqwest::Client::builder()
+                .redirect(redirect_policy)
+                .timeout(timeout);
+            if let Some(max_idle) = max_idle {
+                builder = builder.pool_max_idle_per_host(max_idle);
+            }
+            if let Some(idle_timeout) = idle_timeout {
+                builder = builder.pool_idle_timeout(idle_timeout);
+            }
+            if let Some(identity) = &identity {
+                builder = builder.identity(identity.clone());
+            }
+            for certificate in &root_cas {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            for proxy in proxies {
+                builder = builder.proxy(proxy.clone());
+            }
+            builder
+        };
+        let build = |builder: reqwest::ClientBuilder| {
+            builder
+                .build()
+                .map_err(|e| TraceError::from(format!("failed to build the HTTP client: {e}")))
+        };
+
+        #[cfg(feature = "http3")]
+        if self.http3 {
+            // Policies can't be cloned, so both clients delegate to a shared one.
+            let shared = Arc::new(redirect_policy);
+            let policy = || {
+                let shared = shared.clone();
+                redirect::Policy::custom(move |attempt| shared.redirect(attempt))
+            };
+            return Ok((
+                build(builder(policy()).http3_prior_knowledge())?,
+                Some(build(builder(policy()))?),
+            ));
+        }
+        Ok((build(builder(redirect_policy))?, None))
+    }
+
+    /// Build the exporter without installing a tracer provider, e.g. to register it with a
+    /// custom provider. Give that provider's resource to [`with_resource`](Self::with_resource)
+    /// when sending it [as headers](Self::with_resource_as_headers).
+    pub fn build_exporter(self) -> Result<ParseableExporter, TraceError> {
+        self.build_exporter_for(None)
+    }
+
+    /// Build a `tracing` layer exporting the events recorded outside of any span to the
+    /// `<stream>-logs` stream, with the same endpoint, credentials and request settings
+    /// as the span exporter. Batching follows the [`Signal::Logs`] settings, which
+    /// default to those given to [`with_batch_settings`](Self::with_batch_settings).
+    /// Must be called within a Tokio runtime, which the layer sends its batches on, unless
+    /// the `async-std` feature provides one.
+    pub fn build_log_layer(mut self) -> Result<ParseableLogLayer, TraceError> {
+        let settings = self
+            .batch_settings
+            .unwrap_or_default()
+            .overridden_by_env(Signal::Logs);
+        self.service_name = format!("{}-logs", self.service_name);
+        let exporter = self.build_exporter_for(None)?;
+        ParseableLogLayer::spawn(exporter, settings)
+    }
+
+    /// Build an exporter for the OpenTelemetry metrics SDK, to register with a push
+    /// controller, sending every collected data point as a record to the
+    /// [metrics stream](Self::with_metrics_stream). Collections are exported on the
+    /// Tokio runtime the controller is started on.
+    pub fn build_metrics_exporter(mut self) -> Result<ParseableMetricsExporter, TraceError> {
+        self.service_name = self
+            .metrics_stream
+            .take()
+            .unwrap_or_else(|| format!("{}-metrics", self.service_name));
+        Ok(ParseableMetricsExporter::new(
+            self.build_exporter_for(None)?,
+        ))
+    }
+
+    /// Stream the [metrics exporter](Self::build_metrics_exporter) sends to,
+    /// `<stream>-metrics` by default.
+    pub fn with_metrics_stream<T: Into<String>>(mut self, stream: T) -> Self {
+        self.metrics_stream = Some(stream.into());
+        self
+    }
+
+    fn build_exporter_for(
+        mut self,
+        resource: Option<&sdk::Resource>,
+    ) -> Result<ParseableExporter, TraceError> {
+        self.validate()?;
+        let endpoint = self._build_endpoint()?;
+        let (client, fallback_client) = match self.client.take() {
+            Some(client) => (client, None),
+            None => self.build_clients()?,
+        };
+
+        // We add here the stream name, that will be the name of the service we are going to trace
+        let mut headers = HeaderMap::new();
+        if self.auth.is_none()
+            && self.username == DEFAULT_CREDENTIAL
+            && self.password == DEFAULT_CREDENTIAL
+        {
+            tracing::warn!(
+                stream = %self.service_name,
+                "authorizing with Parseable's default admin/admin credentials; set \
+                 PARSEABLE_USERNAME and PARSEABLE_PASSWORD or call with_auth"
+            );
+        }
+        let authorization = match &self.auth {
+            None => Some(format!(
+                "Basic {}",
+                base64encoder::STANDARD.encode(format!("{}:{}", self.username, self.password))
+            )),
+            Some(Auth::Bearer(token)) => Some(format!("Bearer {token}")),
+            Some(Auth::Basic { .. } | Auth::None) => None,
+        };
+        if let Some(authorization) = authorization {
+            headers.insert(
+                "Authorization",
+                HeaderValue::from_str(&authorization)
+                    .map_err(|e| TraceError::from(format!("invalid Authorization header: {e}")))?,
+            );
+        }
+        headers.insert(
+            "Content-Type",
+            HeaderValue::from_static(self.ingest_format.content_type(&self.api_version)),
+        );
+        headers.insert(
+            "X-P-Stream",
+            HeaderValue::from_str(&self.service_name).map_err(|e| {
+                TraceError::from(format!(
+                    "invalid X-P-Stream header for stream '{}': {e}",
+                    self.service_name
+                ))
+            })?,
+        );
+
+        // Resource attributes
+        let mut message_options = MessageOptions {
+            duration_unit: self.duration_unit,
+            timestamp_precision: self.timestamp_precision,
+            null_handling: self.null_handling,
+            numeric_status: self.numeric_status,
+            string_interning: self.string_interning,
+            sort_by_end_time: self.sort_by_end_time,
+            resource_keys: self.resource_attribute_keys,
+            span_kinds: self.span_kinds,
+            max_trace_depth: self.max_trace_depth,
+            local_root: self.local_root,
+            correlation_id: self.correlation_id,
+            namespaced_attributes: self.namespaced_attributes,
+            envelope: self.envelope_format,
+            attributes: Arc::new(AttributeOptions {
+                key_map: self.attribute_key_map,
+                allowlist: self.attribute_allowlist,
+                normalize: self.value_normalization,
+                coercion: self.type_coercion,
+                units: self.attribute_units,
+                json_keys: self.json_attribute_keys,
+                max_array_elements: self.max_array_elements,
+                typed: self.typed_attributes || self.namespaced_attributes,
+                key_case: self.attribute_key_case,
+                redaction: (!self.redacted_keys.is_empty())
+                    .then(|| Redaction::new(self.redacted_keys, self.redaction_mode)),
+            }),
+            ..Default::default()
+        };
+        if self.resource_as_headers {
+            let configured;
+            let resource = match resource {
+                Some(resource) => resource,
+                None => {
+                    configured = self.resource.take().unwrap_or_default();
+                    &configured
+                }
+            };
+            let mut header_resource = HashMap::new();
+            for (key, value) in resource.iter() {
+                if message_options.attributes.is_redacted(key) {
+                    continue;
+                }
+                if let Some((name, header_value)) = resource_header(key, value) {
+                    headers.insert(name, header_value);
+                    header_resource.insert(key.clone(), value.clone());
+                }
+            }
+            message_options.header_resource = Some(header_resource);
+        }
+        if self.exporter_id {
+            message_options.exporter_id = Some(Uuid::new_v4().to_string());
+        }
+        if self.stream_field {
+            message_options.stream = Some(self.service_name.clone());
+        }
+
+        let schema_publisher = if self.publish_schema && !self.dry_run && self.sink.is_none() {
+            Some(SchemaPublisher::new(
+                &endpoint,
+                &self.service_name,
+                &headers,
+                trace_message_schema(&message_options),
+            )?)
+        } else {
+            None
+        };
+
+        // Metadata
+        if let Some(metadata) = self.metadata {
+            headers.extend(metadata);
+        }
+
+        // Tags
+        if let Some(tags) = self.tags {
+            headers.extend(tags);
+        }
+
+        Ok(ParseableExporter::new(
+            client,
+            fallback_client,
+            endpoint,
+            headers,
+            message_options,
+            RequestOptions {
+                max_connections: self.max_connections_per_host,
+                compression: self.compression,
+                compression_threshold: self.compression_threshold,
+                error_handler: self.error_handler,
+                diagnostics: self.diagnostics.then_some(Diagnostics {
+                    max_body_len: self.diagnostics_body_limit,
+                }),
+                shutdown_grace_period: self.shutdown_grace_period,
+                max_buffered_batches: self.max_buffered_batches,
+                send_queue: self.send_queue,
+                retries: self.retries,
+                health_logging: self.health_logging,
+                mixed_services: self.mixed_services,
+                target_streams: self.target_streams,
+                stream_router: self.stream_router,
+                ingest_format: self.ingest_format,
+                dynamic_headers: self.dynamic_headers,
+                dry_run: self.dry_run || self.sink.is_some(),
+                pretty_json: self.pretty_json,
+                sink: self.sink,
+            },
+            schema_publisher,
+        ))
+    }
+}
+
+/// Group `spans` by the stream they are routed to, in the order the streams first appear.
+fn group_by_stream(
+    spans: Vec<SpanData>,
+    stream_of: impl Fn(&SpanData) -> String,
+) -> Vec<(String, Vec<SpanData>)> {
+    let mut groups: Vec<(String, Vec<SpanData>)> = Vec::new();
+    for span in spans {
+        let stream = stream_of(&span);
+        match groups.iter_mut().find(|(group, _)| *group == stream) {
+            Some((_, group)) => group.push(span),
+            None => groups.push((stream, vec![span])),
+        }
+    }
+    groups
+}
+
+/// The `service.name` of the span's resource.
+fn service_name(span: &SpanData) -> Option<String> {
+    service_name_value(span).map(|service| service.as_str().into_owned())
+}
+
+/// Like [`service_name`], borrowed from the resource.
+fn service_name_value(span: &SpanData) -> Option<&Value> {
+    span.resource
+        .iter()
+        .find(|(key, _)| key.as_str() == "service.name")
+        .map(|(_, service)| service)
+}
+
+/// Drop the spans that the ancestors found in `spans` put more than `max_depth` levels
+/// below their root. A parent missing from `spans` counts as one more level.
+fn retain_within_depth(spans: &mut Vec<SpanData>, max_depth: usize) {
+    let parents: HashMap<(TraceId, SpanId), SpanId> = spans
+        .iter()
+        .map(|span| {
+            let context = &span.span_context;
+            ((context.trace_id(), context.span_id()), span.parent_span_id)
+        })
+        .collect();
+    spans.retain(|span| {
+        let trace_id = span.span_context.trace_id();
+        let mut parent = span.parent_span_id;
+        let mut depth = 0;
+        while parent != SpanId::INVALID {
+            depth += 1;
+            if depth > max_depth {
+                return false;
+            }
+            match parents.get(&(trace_id, parent)) {
+                Some(grandparent) => parent = *grandparent,
+                None => break,
+            }
+        }
+        true
+    });
+}
+
+/// A request body indented, with the records of newline-delimited bodies one after another.
+fn pretty_json(body: &[u8]) -> Result<String, TraceError> {
+    let mut pretty = Vec::new();
+    for value in serde_json::Deserializer::from_slice(body).into_iter::<serde_json::Value>() {
+        let value = value.map_err(|e| TraceError::Other(Box::new(e)))?;
+        pretty.push(
+            serde_json::to_string_pretty(&value).map_err(|e| TraceError::Other(Box::new(e)))?,
+        );
+    }
+    Ok(pretty.join("\n"))
+}
+
+/// Map a resource attribute onto an `X-P-Meta-*` header, if both key and value are header safe.
+fn resource_header(key: &Key, value: &Value) -> Option<(HeaderName, HeaderValue)> {
+    let key = key.as_str();
+    let key_is_safe = !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"._-".contains(&b));
+    let value = value.as_str();
+    if !key_is_safe || value.trim() != value {
+        return None;
+    }
+    let name = HeaderName::from_bytes(format!("X-P-Meta-{key}").as_bytes()).ok()?;
+    let value = HeaderValue::from_str(&value).ok()?;
+    Some((name, value))
+}
+
+/// Tracer of the provider installed last, which only holds a weak reference to it so
+/// the provider is still shut down once the global one is replaced or shut down.
+static INSTALLED_TRACER: Mutex<Option<sdk::trace::Tracer>> = Mutex::new(None);
+
+/// Exports the spans buffered by the tracer provider installed last with
+/// [`install_batch`](ParseableExporterBuilder::install_batch) or
+/// [`install_simple`](ParseableExporterBuilder::install_simple), e.g. at the end of a
+/// serverless invocation, and waits until they are sent. Does nothing once that
+/// provider was shut down. Fails with the first error a span processor reports.
+///
+/// The provider's `force_flush` blocks, so it runs on Tokio's blocking thread pool.
+pub async fn flush() -> TraceResult<()> {
+    let provider = INSTALLED_TRACER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(sdk::trace::Tracer::provider);
+    let Some(provider) = provider else {
+        return Ok(());
+    };
+    runtime::on_tokio(tokio::task::spawn_blocking(move || provider.force_flush()))
+        .await
+        .map_err(|e| TraceError::Other(Box::new(e)))?
+        .into_iter()
+        .collect()
+}
+
+/// Username and password of a fresh Parseable install, used when none are configured.
+const DEFAULT_CREDENTIAL: &str = "admin";
+
+impl Default for ParseableExporterBuilder {
+    fn default() -> Self {
+        ParseableExporterBuilder {
+            tls_enabled: false,
+            host: env::var("PARSEABLE_HOST").unwrap_or_else(|_| "0.0.0.0".into()),
+            port: env::var("PARSEABLE_PORT").unwrap_or_else(|_| "8000".into()),
+            base_path: String::new(),
+            endpoint: None,
+            api_version: ParseableApiVersion::V1,
+            username: env::var("PARSEABLE_USERNAME").unwrap_or_else(|_| DEFAULT_CREDENTIAL.into()),
+            password: env::var("PARSEABLE_PASSWORD").unwrap_or_else(|_| DEFAULT_CREDENTIAL.into()),
+            auth: None,
+            timeout: Duration::from_secs(30),
+            client_identity: None,
+            root_cas: Vec::new(),
+            service_name: "my-service".into(),
+            metrics_stream: None,
+            client: None,
+            max_connections_per_host: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            proxies: Vec::new(),
+            #[cfg(feature = "http3")]
+            http3: false,
+            redirect_policy: None,
+            resource_as_headers: false,
+            resource: None,
+            resource_attribute_keys: None,
+            resource_attributes: Vec::new(),
+            exporter_id: false,
+            stream_field: false,
+            publish_schema: false,
+            string_interning: false,
+            sort_by_end_time: false,
+            span_kinds: None,
+            max_trace_depth: None,
+            local_root: false,
+            correlation_id: false,
+            attribute_key_map: None,
+            attribute_allowlist: None,
+            value_normalization: None,
+            type_coercion: None,
+            attribute_units: None,
+            json_attribute_keys: None,
+            max_array_elements: None,
+            typed_attributes: false,
+            namespaced_attributes: false,
+            envelope_format: false,
+            ingest_format: IngestFormat::JsonArray,
+            attribute_key_case: CaseMode::AsIs,
+            redacted_keys: Vec::new(),
+            redaction_mode: RedactionMode::Mask,
+            duration_unit: DurationUnit::Millis,
+            timestamp_precision: TimestampPrecision::Millis,
+            null_handling: NullHandling::OmitNull,
+            numeric_status: false,
+            compression: Compression::None,
+            compression_threshold: 1024,
+            error_handler: None,
+            diagnostics: env::var("OTLP_DEBUG").is_ok_and(|debug| debug == "1"),
+            diagnostics_body_limit: 2048,
+            shutdown_grace_period: Duration::from_secs(5),
+            max_buffered_batches: None,
+            send_queue: None,
+            retries: Retries {
+                max_failure_retries: 3,
+                initial_backoff: Duration::from_millis(200),
+                max_rate_limit_retries: 3,
+                max_wait: Duration::from_secs(10),
+                clock: Arc::new(SystemClock),
+                jitter: JitterStrategy::Full,
+            },
+            health_logging: None,
+            mixed_services: MixedServices::Warn,
+            target_streams: None,
+            stream_router: None,
+            startup_marker: false,
+            batch_settings: None,
+            max_queue_size: None,
+            max_export_batch_size: None,
+            scheduled_delay: None,
+            batch_export_timeout: None,
+            span_processors: Vec::new(),
+            byte_flush: None,
+            min_batch_size: None,
+            metadata: None,
+            tags: None,
+            dynamic_headers: None,
+            dry_run: false,
+            pretty_json: false,
+            sink: None,
+        }
+    }
+}
+
+impl export::trace::SpanExporter for ParseableExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, export::trace::ExportResult> {
+        Box::pin(runtime::on_tokio(self.export_batch(batch)))
+    }
+
+    /// Waits up to the shutdown grace period for exports still in flight, e.g. ones the
+    /// batch processor gave up on after its export timeout, so their requests finish.
+    /// The wait blocks the calling thread, so the exports need another runtime thread
+    /// to make progress on.
+    fn shutdown(&mut self) {
+        if !self
+            .in_flight
+            .wait_idle(self.request_options.shutdown_grace_period)
+        {
+            global::handle_error(TraceError::from(
+                "exports still in flight after the shutdown grace period",
+            ));
+        }
+    }
+}
+
+impl ParseableExporter {
+    /// Sends the batch to its streams, queued or right away.
+    fn export_batch(
+        &self,
+        batch: Vec<SpanData>,
+    ) -> BoxFuture<'static, export::trace::ExportResult> {
+        let stream = self.stream();
+        let split_services = match self.request_options.mixed_services {
+            MixedServices::Warn => {
+                let mut services = batch.iter().filter_map(service_name_value);
+                if let Some(first) = services.next() {
+                    if services.any(|service| service != first) {
+                        self.report_mixed_services(&batch, &stream);
+                    }
+                }
+                false
+            }
+            MixedServices::Split => true,
+        };
+        let target_streams = self.request_options.target_streams.as_ref();
+        let stream_router = self.request_options.stream_router.as_ref();
+        if stream_router.is_none() && target_streams.is_none() && !split_services {
+            return self.export_to(batch, stream);
+        }
+        let stream_of = |span: &SpanData| {
+            stream_router
+                .and_then(|StreamRouter(router)| router(span))
+                .or_else(|| {
+                    target_streams
+                        .and_then(|target_streams| target_streams.stream_for(span))
+                        .map(str::to_string)
+                })
+                .or_else(|| split_services.then(|| service_name(span)).flatten())
+                .unwrap_or_else(|| stream.clone())
+        };
+        let mut exports: Vec<_> = group_by_stream(batch, stream_of)
+            .into_iter()
+            .map(|(stream, spans)| self.export_to(spans, stream))
+            .collect();
+        if exports.len() == 1 {
+            return exports.remove(0);
+        }
+        Box::pin(async move {
+            let mut result = Ok(());
+            for export in exports {
+                if let Err(e) = export.await {
+                    result = Err(e);
+                }
+            }
+            result
+        })
+    }
+
+    /// Clock the exporter reads the current time from.
+    pub(crate) fn clock(&self) -> Arc<dyn Clock> {
+        self.request_options.retries.clock.clone()
+    }
+
+    /// Report a batch of several services to the global error handler, unless a batch of
+    /// the same services was reported before. It isn't logged with `tracing`, where the
+    /// log layer would export every report again.
+    fn report_mixed_services(&self, batch: &[SpanData], stream: &str) {
+        let services: BTreeSet<String> = batch
+            .iter()
+            .filter_map(service_name_value)
+            .map(|service| service.as_str().into_owned())
+            .collect();
+        let mut reported = self.mixed_services_reported.lock().unwrap();
+        if reported.contains(&services) {
+            return;
+        }
+        let error = TraceError::from(format!(
+            "batch holds spans of the services {services:?}, sending all of them to stream '{stream}'"
+        ));
+        reported.insert(services);
+        drop(reported);
+        global::handle_error(error);
+    }
+
+    /// Whether the resource attribute is sent as a header instead of in the records.
+    pub(crate) fn sent_as_header(&self, key: &Key, value: &Value) -> bool {
+        self.message_options.sent_as_header(key, value)
+    }
+
+    /// Precision the timestamps of records other than spans are formatted with.
+    pub(crate) fn timestamp_precision(&self) -> TimestampPrecision {
+        self.message_options.timestamp_precision
+    }
+
+    /// Pool formatting the attributes of records other than spans, e.g. logs, with the
+    /// same options, redaction included, as span attributes.
+    pub(crate) fn attribute_pool(&self) -> StringPool {
+        StringPool::new(
+            self.message_options.string_interning,
+            self.message_options.attributes.clone(),
+        )
+    }
+
+    /// Send records other than spans, e.g. logs, to the exporter's stream, reporting
+    /// failures to the error handler like failed exports.
+    pub(crate) fn send_records<T: Serialize>(
+        &self,
+        records: &[T],
+    ) -> BoxFuture<'static, export::trace::ExportResult> {
+        let stream = self.stream();
+        let request = self
+            .request_options
+            .ingest_format
+            .serialize(records)
+            .map_err(|e| TraceError::Other(Box::new(e)))
+            .and_then(|body| self.request_with_body(body, None));
+        let in_flight = self.in_flight.start();
         let client = self.client.clone();
+        let fallback_client = self.fallback_client.clone();
+        let connection_limit = self.connection_limit.clone();
+        let error_handler = self.request_options.error_handler.clone();
+        let diagnostics = self.request_options.diagnostics;
+        let dry_run = self.request_options.dry_run;
+        let retries = self.request_options.retries.clone();
+        let stats = self.stats.clone();
+        let message_count = records.len();
 
-        Box::pin({
-            async move {
-                client
-                    .execute(req?)
-                    .await
-                    .map_err(|e| TraceError::Other(Box::new(e)))?;
-                Ok(())
+        Box::pin(async move {
+            let _in_flight = in_flight;
+            let result = send_request(
+                client,
+                fallback_client,
+                request,
+                connection_limit,
+                diagnostics,
+                retries,
+                dry_run,
+            )
+            .await;
+            stats.record(result.is_ok(), message_count);
+            result.map_err(|failure| {
+                failure.report(error_handler.as_ref(), stream, message_count);
+                failure.error
+            })
+        })
+    }
+
+    /// Export `batch` to `stream`, which may differ from the exporter's stream when
+    /// splitting a batch of several services.
+    fn export_to(
+        &self,
+        batch: Vec<SpanData>,
+        stream: String,
+    ) -> BoxFuture<'static, export::trace::ExportResult> {
+        let mut traces = into_trace_messages(batch, &self.message_options);
+        // Flushing with nothing queued hands over an empty batch, and filtering may leave
+        // nothing of one; there is nothing to send.
+        if traces.is_empty() {
+            return Box::pin(async { Ok(()) });
+        }
+        let default_stream = stream == self.stream();
+        if !default_stream && self.message_options.stream.is_some() {
+            for trace in &mut traces {
+                trace.stream = Some(stream.clone());
+            }
+        }
+        self.stats.start_health_logging(|| self.stream());
+        let in_flight = match self.start_export(traces.len()) {
+            Ok(in_flight) => in_flight,
+            Err(failure) => {
+                self.stats.record(false, traces.len());
+                failure.report(
+                    self.request_options.error_handler.as_ref(),
+                    stream,
+                    traces.len(),
+                );
+                return Box::pin(async { Err(failure.error) });
+            }
+        };
+        let request = self.build_request(&mut traces, (!default_stream).then_some(&stream));
+        if let Some(send_queue) = &self.send_queue {
+            return self.queue_request(send_queue, request, traces.len(), stream, in_flight);
+        }
+        let client = self.client.clone();
+        let fallback_client = self.fallback_client.clone();
+        let connection_limit = self.connection_limit.clone();
+        let schema_publisher = self.schema_publisher.clone();
+        let error_handler = self.request_options.error_handler.clone();
+        let diagnostics = self.request_options.diagnostics;
+        let dry_run = self.request_options.dry_run;
+        let retries = self.request_options.retries.clone();
+        let stats = self.stats.clone();
+        let message_count = traces.len();
+
+        Box::pin(async move {
+            let _in_flight = in_flight;
+            if let Some(schema_publisher) = schema_publisher {
+                schema_publisher.ensure_published(&client).await;
+            }
+            match send_request(
+                client,
+                fallback_client,
+                request,
+                connection_limit,
+                diagnostics,
+                retries,
+                dry_run,
+            )
+            .await
+            {
+                Ok(_) => {
+                    stats.record(true, message_count);
+                    Ok(())
+                }
+                Err(failure) => {
+                    stats.record(false, message_count);
+                    failure.report(error_handler.as_ref(), stream, message_count);
+                    Err(failure.error)
+                }
             }
         })
     }
 }
 
+/// Execute `request`, retrying once over `fallback_client` if it fails without a response.
+async fn execute(
+    client: &reqwest::Client,
+    fallback_client: Option<&reqwest::Client>,
+    request: reqwest::Request,
+) -> Result<reqwest::Response, SendFailure> {
+    let retry = fallback_client.and_then(|fallback| Some((fallback, request.try_clone()?)));
+    match (client.execute(request).await, retry) {
+        (Ok(response), _) => Ok(response),
+        (Err(_), Some((fallback, request))) => fallback
+            .execute(request)
+            .await
+            .map_err(|e| TraceError::Other(Box::new(e)).into()),
+        (Err(e), None) => Err(TraceError::Other(Box::new(e)).into()),
+    }
+}
+
+/// Delay asked for by a response's `Retry-After` header, given in seconds or as an HTTP
+/// date.
+fn retry_after(response: &reqwest::Response, clock: &dyn Clock) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        SystemTime::from(date)
+            .duration_since(clock.now())
+            .unwrap_or_default(),
+    )
+}
+
+/// Response bodies of rejected requests are kept up to this many bytes.
+const MAX_RESPONSE_BODY_LEN: usize = 4096;
+
+/// Send `request`, failing with the status and the start of the body when Parseable
+/// doesn't accept it with a 2xx response.
+async fn send_request(
+    client: Arc<reqwest::Client>,
+    fallback_client: Option<Arc<reqwest::Client>>,
+    request: Result<reqwest::Request, TraceError>,
+    connection_limit: Option<Arc<Semaphore>>,
+    diagnostics: Option<Diagnostics>,
+    retries: Retries,
+    dry_run: bool,
+) -> Result<(), SendFailure> {
+    let _permit = match connection_limit {
+        Some(limit) => Some(
+            limit
+                .acquire_owned()
+                .await
+                .map_err(|e| TraceError::Other(Box::new(e)))?,
+        ),
+        None => None,
+    };
+    let mut request = request?;
+    if dry_run {
+        return Ok(());
+    }
+    let described_request = diagnostics.map(|diagnostics| diagnostics.describe_request(&request));
+    let mut failures = 0;
+    let mut failure_backoff = retries.initial_backoff;
+    let mut rate_limited = 0;
+    let mut waited = Duration::ZERO;
+    let mut backoff = RATE_LIMIT_BACKOFF;
+    let response = loop {
+        let next_attempt = request.try_clone();
+        let response = match execute(&client, fallback_client.as_deref(), request).await {
+            Ok(response) if !response.status().is_server_error() => response,
+            result => match next_attempt {
+                Some(next_attempt) if failures < retries.max_failure_retries => {
+                    failure_backoff = retries.jitter.delay(
+                        retries.initial_backoff,
+                        MAX_FAILURE_BACKOFF,
+                        failures,
+                        failure_backoff,
+                    );
+                    tokio::time::sleep(failure_backoff).await;
+                    failures += 1;
+                    request = next_attempt;
+                    continue;
+                }
+                _ => break result?,
+            },
+        };
+        if response.status() != StatusCode::TOO_MANY_REQUESTS
+            || rate_limited >= retries.max_rate_limit_retries
+        {
+            break response;
+        }
+        backoff = retries.jitter.delay(
+            RATE_LIMIT_BACKOFF,
+            retries.max_wait,
+            rate_limited as u32,
+            backoff,
+        );
+        let delay = retry_after(&response, retries.clock.as_ref()).unwrap_or_default() + backoff;
+        match next_attempt {
+            Some(next_attempt) if waited + delay <= retries.max_wait => {
+                tokio::time::sleep(delay).await;
+                waited += delay;
+                rate_limited += 1;
+                request = next_attempt;
+            }
+            _ => break response,
+        }
+    };
+
+    let status = response.status();
+    if status.is_redirection() {
+        let location = response
+            .headers()
+            .get(http::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .unwrap_or_default();
+        return Err(SendFailure {
+            error: TraceError::from(format!(
+                "Parseable responded with {} redirecting to '{}', check the configured endpoint",
+                status, location
+            )),
+            status: Some(status),
+            body: None,
+        });
+    }
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = truncate(
+        response.text().await.unwrap_or_default(),
+        MAX_RESPONSE_BODY_LEN,
+    );
+    if let (Some(diagnostics), Some(request)) = (diagnostics, described_request) {
+        diagnostics.log_rejection(&request, status, &body);
+    }
+    Err(SendFailure {
+        error: TraceError::from(format!("Parseable responded with {status}: {body}")),
+        status: Some(status),
+        body: Some(body),
+    })
+}
+
+/// The resource attributes of a span's records, `None` when filtering leaves none.
+fn extract_resource_attributes(
+    resource: &sdk::Resource,
+    options: &MessageOptions,
+    pool: &mut StringPool,
+) -> Option<Attributes> {
+    if options.header_resource.is_none() && options.resource_keys.is_none() {
+        return Some(extract_attributes(resource.iter(), pool));
+    }
+    let remaining = extract_attributes(
+        resource.iter().filter(|(key, value)| {
+            !options.sent_as_header(key, value)
+                && options
+                    .resource_keys
+                    .as_ref()
+                    .is_none_or(|resource_keys| resource_keys.contains(*key))
+        }),
+        pool,
+    );
+    (!remaining.is_empty()).then_some(remaining)
+}
+
 /// Convert span data into flattened trace data.  
-fn into_trace_messages(spans: Vec<SpanData>) -> Vec<TraceMessage> {
+fn into_trace_messages(mut spans: Vec<SpanData>, options: &MessageOptions) -> Vec<TraceMessage> {
+    let batch_spans: Option<HashSet<(TraceId, SpanId)>> = options.local_root.then(|| {
+        spans
+            .iter()
+            .map(|span| (span.span_context.trace_id(), span.span_context.span_id()))
+            .collect()
+    });
+    if let Some(max_depth) = options.max_trace_depth {
+        retain_within_depth(&mut spans, max_depth);
+    }
+    if let Some(kinds) = &options.span_kinds {
+        spans.retain(|span| kinds.contains(&span.span_kind));
+    }
+    if options.sort_by_end_time {
+        spans.sort_by_key(|span| span.end_time);
+    }
     let mut trace_messages = Vec::with_capacity(spans.len());
+    let mut pool = StringPool::new(options.string_interning, options.attributes.clone());
+    // Spans of one provider share their resource, so it's only extracted again when a
+    // span's resource differs from the previous one's.
+    let mut resource_cache: Option<(Cow<'static, sdk::Resource>, Option<Attributes>)> = None;
 
     for span in spans {
-        let start_time = to_timestamp_string(span.start_time);
-        let end_time = to_timestamp_string(span.end_time);
+        let start_time = options.timestamp_precision.format(span.start_time);
+        let end_time = options.timestamp_precision.format(span.end_time);
+        let duration = SpanDuration::between(span.start_time, span.end_time, options.duration_unit);
+        let resource_attributes = match &resource_cache {
+            Some((resource, attributes)) if *resource == span.resource => attributes.clone(),
+            _ => {
+                let attributes = extract_resource_attributes(&span.resource, options, &mut pool);
+                resource_cache = Some((span.resource.clone(), attributes.clone()));
+                attributes
+            }
+        };
         let trace_message = TraceMessage {
-            resource_attributes: extract_attributes(span.resource.iter()),
+            resource_attributes: Nullable::new(resource_attributes, options.null_handling),
             span_name: span.name.to_string(),
-            attributes: extract_attributes(span.attributes.iter()),
+            span_kind: match span.span_kind {
+                SpanKind::Client => "client",
+                SpanKind::Server => "server",
+                SpanKind::Producer => "producer",
+                SpanKind::Consumer => "consumer",
+                SpanKind::Internal => "internal",
+            },
+            attributes: extract_span_attributes(
+                span.attributes.iter().filter(|(key, _)| {
+                    !options.correlation_id || key.as_str() != CORRELATION_ID_ATTRIBUTE
+                }),
+                &mut pool,
+            ),
             start_time,
             end_time,
+            duration,
             parent_span_id: span.parent_span_id.to_string(),
             span_id: span.span_context.span_id().to_string(),
             trace_id: span.span_context.trace_id().to_string(),
-            event_message: None,
-            event_timestamp: None,
+            trace_state: span.span_context.trace_state().header(),
+            sampled: span.span_context.is_sampled(),
+            links: span
+                .links
+                .iter()
+                .map(|link| SpanLink {
+                    trace_id: link.span_context.trace_id().to_string(),
+                    span_id: link.span_context.span_id().to_string(),
+                    attributes: extract_span_attributes(
+                        link.attributes.iter().map(|kv| (&kv.key, &kv.value)),
+                        &mut pool,
+                    ),
+                })
+                .collect(),
+            dropped_attributes_count: span.attributes.dropped_count(),
+            dropped_events_count: span.events.dropped_count(),
+            local_root: batch_spans.as_ref().map(|batch_spans| {
+                span.parent_span_id == SpanId::INVALID
+                    || !batch_spans.contains(&(span.span_context.trace_id(), span.parent_span_id))
+            }),
+            status_code: SpanStatus::new(&span.status, options.numeric_status),
+            status_message: match &span.status {
+                Status::Error { description } if !description.is_empty() => {
+                    Some(description.to_string())
+                }
+                _ => None,
+            },
+            event_message: Nullable::new(None, options.null_handling),
+            event_timestamp: Nullable::new(None, options.null_handling),
+            event_attributes: Nullable::new(None, options.null_handling),
+            correlation_id: options
+                .correlation_id
+                .then(|| {
+                    span.attributes
+                        .get(&Key::from_static_str(CORRELATION_ID_ATTRIBUTE))
+                })
+                .flatten()
+                .map(|value| value.as_str().into_owned()),
+            exporter_id: options.exporter_id.clone(),
+            stream: options.stream.clone(),
         };
 
         if span.events.is_empty() {
@@ -322,25 +2947,22 @@ fn into_trace_messages(spans: Vec<SpanData>) -> Vec<TraceMessage> {
         } else {
             trace_messages.extend(span.events.into_iter().map(|event| {
                 let mut trace_message = trace_message.clone();
-                trace_message.attributes.extend(extract_attributes(
-                    event.attributes.iter().map(|kv| (&kv.key, dbg!(&kv.value))),
+                trace_message.event_attributes = Nullable::Value(extract_span_attributes(
+                    event.attributes.iter().map(|kv| (&kv.key, &kv.value)),
+                    &mut pool,
                 ));
-                trace_message.event_message = Some(event.name.to_string());
-                trace_message.event_timestamp = Some(to_timestamp_string(event.timestamp));
+                trace_message.event_message = Nullable::Value(event.name.to_string());
+                trace_message.event_timestamp =
+                    Nullable::Value(options.timestamp_precision.format(event.timestamp));
                 trace_message
             }))
         }
     }
 
+    if options.namespaced_attributes {
+        trace_messages
+            .iter_mut()
+            .for_each(TraceMessage::namespace_attributes);
+    }
     trace_messages
 }
-
-fn to_timestamp_string(timestamp: SystemTime) -> String {
-    DateTime::<Utc>::from(timestamp).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
-}
-
-fn extract_attributes<'a>(attributes: impl Iterator<Item = (&'a Key, &'a Value)>) -> Vec<String> {
-    attributes
-        .map(|(key, value)| format!("{}={}", key, value))
-        .collect()
-}
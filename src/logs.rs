@@ -0,0 +1,225 @@
+use crate::{
+    attributes::{extract_span_attributes, Attributes, StringPool},
+    BatchSettings, Clock, ParseableExporter, TimestampPrecision,
+};
+use opentelemetry::{global, trace::TraceError, Key, Value};
+use serde::Serialize;
+use std::{fmt, sync::Arc, time::SystemTime};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// Targets whose events are never exported, as sending them would log more events: the
+/// exporter's own and those of the HTTP stack it sends with.
+const SKIPPED_TARGETS: [&str; 4] = ["opentelemetry_parseable", "reqwest", "hyper", "h2"];
+
+/// A `tracing` event recorded outside of any span, with its fields not yet formatted.
+#[derive(Debug)]
+struct LogEvent {
+    timestamp: SystemTime,
+    level: &'static str,
+    target: String,
+    message: String,
+    fields: Vec<(Key, Value)>,
+}
+
+impl LogEvent {
+    fn new(event: &Event<'_>, clock: &dyn Clock) -> Self {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        LogEvent {
+            timestamp: clock.now(),
+            level: metadata.level().as_str(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        }
+    }
+}
+
+/// A [`LogEvent`] as sent to Parseable, its fields formatted like span attributes.
+#[derive(Serialize, Debug)]
+pub(crate) struct LogRecord {
+    timestamp: String,
+    level: &'static str,
+    target: String,
+    message: String,
+    attributes: Attributes,
+}
+
+impl LogRecord {
+    fn new(event: LogEvent, pool: &mut StringPool, precision: TimestampPrecision) -> Self {
+        LogRecord {
+            timestamp: precision.format(event.timestamp),
+            level: event.level,
+            target: event.target,
+            message: event.message,
+            attributes: extract_span_attributes(
+                event.fields.iter().map(|(key, value)| (key, value)),
+                pool,
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<(Key, Value)>,
+}
+
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: Value) {
+        self.fields
+            .push((Key::from_static_str(field.name()), value));
+    }
+}
+
+/// Numbers and booleans keep their type, so typed attributes export them as JSON numbers
+/// and booleans; `u64` values beyond `i64::MAX` are exported as strings.
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Value::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.push(field, Value::I64(value)),
+            Err(_) => self.push(field, Value::String(value.to_string().into())),
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, Value::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Value::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.push(field, Value::String(value.to_string().into()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.push(field, Value::String(format!("{value:?}").into()));
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Record(LogEvent),
+    Flush(oneshot::Sender<()>),
+}
+
+/// `tracing` layer exporting the events recorded outside of any span to Parseable, built
+/// by [`build_log_layer`](crate::ParseableExporterBuilder::build_log_layer). Events
+/// within a span are already exported as events of the span.
+///
+/// Events are batched like spans, by the [`Signal::Logs`](crate::Signal::Logs) batch
+/// settings, and dropped when the queue is full. Clones share the queue, so a clone kept
+/// aside can [`flush`](Self::flush) it before the process exits.
+#[derive(Debug, Clone)]
+pub struct ParseableLogLayer {
+    sender: mpsc::Sender<Message>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ParseableLogLayer {
+    /// Start the task exporting the batches on the current Tokio runtime.
+    pub(crate) fn spawn(
+        exporter: ParseableExporter,
+        settings: BatchSettings,
+    ) -> Result<Self, TraceError> {
+        let runtime = crate::runtime::handle().ok_or_else(|| {
+            TraceError::from("the log layer must be built within a Tokio runtime")
+        })?;
+        let (sender, mut receiver) = mpsc::channel(settings.max_queue_size.max(1));
+        let clock = exporter.clock();
+        runtime.spawn(async move {
+            let mut interval = tokio::time::interval(settings.scheduled_delay);
+            let mut buffer = Vec::new();
+            loop {
+                let message = tokio::select! {
+                    message = receiver.recv() => message,
+                    _ = interval.tick() => {
+                        send(&exporter, &mut buffer).await;
+                        continue;
+                    }
+                };
+                match message {
+                    Some(Message::Record(record)) => {
+                        buffer.push(record);
+                        if buffer.len() >= settings.max_export_batch_size {
+                            send(&exporter, &mut buffer).await;
+                        }
+                    }
+                    Some(Message::Flush(done)) => {
+                        send(&exporter, &mut buffer).await;
+                        let _ = done.send(());
+                    }
+                    None => {
+                        send(&exporter, &mut buffer).await;
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(ParseableLogLayer { sender, clock })
+    }
+
+    /// Export the events queued so far and wait for the request to complete.
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).await.is_ok() {
+            let _ = flushed.await;
+        }
+    }
+}
+
+impl<S> Layer<S> for ParseableLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let target = event.metadata().target();
+        if ctx.event_span(event).is_some()
+            || SKIPPED_TARGETS
+                .iter()
+                .any(|skipped| target.split("::").next() == Some(skipped))
+        {
+            return;
+        }
+        let _ = self
+            .sender
+            .try_send(Message::Record(LogEvent::new(event, self.clock.as_ref())));
+    }
+}
+
+/// Send the buffered records, reporting failures like those of span exports.
+async fn send(exporter: &ParseableExporter, buffer: &mut Vec<LogEvent>) {
+    if buffer.is_empty() {
+        return;
+    }
+    let mut pool = exporter.attribute_pool();
+    let precision = exporter.timestamp_precision();
+    let records: Vec<LogRecord> = std::mem::take(buffer)
+        .into_iter()
+        .map(|event| LogRecord::new(event, &mut pool, precision))
+        .collect();
+    if let Err(err) = exporter.send_records(&records).await {
+        global::handle_error(err);
+    }
+}
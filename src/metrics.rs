@@ -0,0 +1,153 @@
+use crate::{
+    attributes::{extract_attributes, extract_span_attributes, Attributes, StringPool},
+    ParseableExporter, TimestampPrecision,
+};
+use opentelemetry::{
+    global,
+    metrics::{MetricsError, Result},
+    sdk::{
+        export::metrics::{
+            aggregation::{
+                AggregationKind, Count, LastValue, Sum, Temporality, TemporalitySelector,
+            },
+            InstrumentationLibraryReader, MetricsExporter, Record,
+        },
+        metrics::{
+            aggregators::{HistogramAggregator, LastValueAggregator, SumAggregator},
+            sdk_api::Descriptor,
+        },
+        Resource,
+    },
+    Context,
+};
+use serde::Serialize;
+
+/// A data point of a metric, as aggregated over one collection.
+#[derive(Serialize, Debug)]
+struct MetricRecord {
+    metric_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+    /// `sum`, `last_value` or `histogram`.
+    aggregation: &'static str,
+    temporality: &'static str,
+    /// The sum or last value, or for histograms the sum of the recorded values.
+    value: f64,
+    /// Number of values recorded by a histogram.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<u64>,
+    start_time: String,
+    end_time: String,
+    attributes: Attributes,
+    resource_attributes: Attributes,
+}
+
+impl MetricRecord {
+    fn new(
+        record: &Record<'_>,
+        resource_attributes: &Attributes,
+        pool: &mut StringPool,
+        precision: TimestampPrecision,
+    ) -> Result<Self> {
+        let descriptor = record.descriptor();
+        let number_kind = descriptor.number_kind();
+        let aggregator = record.aggregator().ok_or(MetricsError::NoDataCollected)?;
+        let aggregator = aggregator.as_any();
+        let (aggregation, value, count) =
+            if let Some(histogram) = aggregator.downcast_ref::<HistogramAggregator>() {
+                let sum = histogram.sum()?.to_f64(number_kind);
+                ("histogram", sum, Some(histogram.count()?))
+            } else if let Some(sum) = aggregator.downcast_ref::<SumAggregator>() {
+                ("sum", sum.sum()?.to_f64(number_kind), None)
+            } else if let Some(last_value) = aggregator.downcast_ref::<LastValueAggregator>() {
+                let (value, _) = last_value.last_value()?;
+                ("last_value", value.to_f64(number_kind), None)
+            } else {
+                return Err(MetricsError::Other(format!(
+                    "unsupported aggregation of metric '{}'",
+                    descriptor.name()
+                )));
+            };
+        Ok(MetricRecord {
+            metric_name: descriptor.name().to_string(),
+            description: descriptor.description().cloned(),
+            unit: descriptor.unit().map(str::to_string),
+            aggregation,
+            temporality: "cumulative",
+            value,
+            count,
+            start_time: precision.format(*record.start_time()),
+            end_time: precision.format(*record.end_time()),
+            attributes: extract_span_attributes(record.attributes().iter(), pool),
+            resource_attributes: resource_attributes.clone(),
+        })
+    }
+}
+
+/// Metrics exporter sending collected data points to Parseable, built by
+/// [`build_metrics_exporter`](crate::ParseableExporterBuilder::build_metrics_exporter).
+///
+/// Every collection is sent as one batch of records carrying the metric name, its value
+/// and the attributes of the data point. Values are exported cumulatively, as sums and
+/// counts since the start of the process. An export only queues the request on the
+/// current Tokio runtime; delivery failures go to the error handlers.
+#[derive(Debug)]
+pub struct ParseableMetricsExporter {
+    exporter: ParseableExporter,
+}
+
+impl ParseableMetricsExporter {
+    pub(crate) fn new(exporter: ParseableExporter) -> Self {
+        ParseableMetricsExporter { exporter }
+    }
+}
+
+impl TemporalitySelector for ParseableMetricsExporter {
+    fn temporality_for(&self, _descriptor: &Descriptor, _kind: &AggregationKind) -> Temporality {
+        Temporality::Cumulative
+    }
+}
+
+impl MetricsExporter for ParseableMetricsExporter {
+    fn export(
+        &self,
+        _cx: &Context,
+        resource: &Resource,
+        reader: &dyn InstrumentationLibraryReader,
+    ) -> Result<()> {
+        let mut pool = self.exporter.attribute_pool();
+        let resource_attributes = extract_attributes(
+            resource
+                .iter()
+                .filter(|(key, value)| !self.exporter.sent_as_header(key, value)),
+            &mut pool,
+        );
+        let precision = self.exporter.timestamp_precision();
+        let mut records = Vec::new();
+        reader.try_for_each(&mut |_library, reader| {
+            reader.try_for_each(self, &mut |record| {
+                records.push(MetricRecord::new(
+                    record,
+                    &resource_attributes,
+                    &mut pool,
+                    precision,
+                )?);
+                Ok(())
+            })
+        })?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        let runtime = crate::runtime::handle()
+            .ok_or_else(|| MetricsError::Other("no Tokio runtime to send the metrics on".into()))?;
+        let send = self.exporter.send_records(&records);
+        runtime.spawn(async move {
+            if let Err(err) = send.await {
+                global::handle_error(err);
+            }
+        });
+        Ok(())
+    }
+}
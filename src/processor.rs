@@ -0,0 +1,256 @@
+use crate::BatchSettings;
+use opentelemetry::runtime::Runtime;
+use opentelemetry::{
+    baggage::BaggageExt,
+    global,
+    sdk::{
+        export::trace::{ExportResult, SpanData, SpanExporter},
+        trace::{Span, SpanProcessor},
+    },
+    trace::{Span as _, TraceError, TraceResult},
+    Context, Key, KeyValue, Value,
+};
+use std::{
+    future::{poll_fn, Future},
+    pin::Pin,
+    sync::mpsc as std_mpsc,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+/// Fixed allowance per record for ids, timestamps, the duration and JSON punctuation.
+const RECORD_OVERHEAD: usize = 256;
+
+enum Message {
+    Span(Box<SpanData>),
+    Flush(std_mpsc::Sender<ExportResult>),
+    Shutdown(std_mpsc::Sender<ExportResult>),
+}
+
+/// Span processor that batches spans like the SDK's `BatchSpanProcessor`, and also exports
+/// as soon as the estimated serialized size of the buffered spans reaches `max_bytes`.
+///
+/// The count and time triggers of [`BatchSettings`] still apply: a batch is exported when
+/// it holds `max_export_batch_size` spans, every `scheduled_delay`, or when it reaches
+/// `max_bytes`, whichever comes first. A single span larger than `max_bytes` is exported on
+/// its own.
+///
+/// Installed by [`ParseableExporterBuilder::with_min_batch_size`](crate::ParseableExporterBuilder::with_min_batch_size),
+/// the processor also skips scheduled exports of fewer spans than the minimum until the
+/// oldest of them reaches the maximum age.
+#[derive(Debug)]
+pub struct ByteBoundedProcessor {
+    sender: mpsc::Sender<Message>,
+}
+
+impl ByteBoundedProcessor {
+    pub fn new<E, R>(exporter: E, runtime: R, settings: BatchSettings, max_bytes: usize) -> Self
+    where
+        E: SpanExporter + 'static,
+        R: Runtime,
+    {
+        Self::spawn(exporter, runtime, settings, max_bytes, None)
+    }
+
+    /// Like [`new`](Self::new), with scheduled exports waiting for `min_batch`, the
+    /// minimum number of spans and the longest time the oldest of them may be held.
+    pub(crate) fn spawn<E, R>(
+        mut exporter: E,
+        runtime: R,
+        settings: BatchSettings,
+        max_bytes: usize,
+        min_batch: Option<(usize, Duration)>,
+    ) -> Self
+    where
+        E: SpanExporter + 'static,
+        R: Runtime,
+    {
+        let (sender, mut receiver) = mpsc::channel(settings.max_queue_size.max(1));
+        let timer = runtime.clone();
+
+        runtime.spawn(Box::pin(async move {
+            let mut delay = timer.delay(settings.scheduled_delay);
+            let mut buffer = Vec::new();
+            let mut buffered_bytes = 0;
+            let mut oldest = Instant::now();
+            loop {
+                let message = tokio::select! {
+                    message = receiver.recv() => message,
+                    _ = elapsed(&mut delay) => {
+                        delay = timer.delay(settings.scheduled_delay);
+                        if let Some((min_spans, max_age)) = min_batch {
+                            if buffer.len() < min_spans && oldest.elapsed() < max_age {
+                                continue;
+                            }
+                        }
+                        report(export(&mut exporter, &mut buffer, &mut buffered_bytes).await);
+                        continue;
+                    }
+                };
+                match message {
+                    Some(Message::Span(span)) => {
+                        if buffer.is_empty() {
+                            oldest = Instant::now();
+                        }
+                        buffered_bytes += estimated_size(&span);
+                        buffer.push(*span);
+                        if buffer.len() >= settings.max_export_batch_size
+                            || buffered_bytes >= max_bytes
+                        {
+                            report(export(&mut exporter, &mut buffer, &mut buffered_bytes).await);
+                        }
+                    }
+                    Some(Message::Flush(result)) => {
+                        let _ = result
+                            .send(export(&mut exporter, &mut buffer, &mut buffered_bytes).await);
+                    }
+                    Some(Message::Shutdown(result)) => {
+                        let flushed = export(&mut exporter, &mut buffer, &mut buffered_bytes).await;
+                        exporter.shutdown();
+                        let _ = result.send(flushed);
+                        break;
+                    }
+                    None => {
+                        report(export(&mut exporter, &mut buffer, &mut buffered_bytes).await);
+                        exporter.shutdown();
+                        break;
+                    }
+                }
+            }
+        }));
+
+        ByteBoundedProcessor { sender }
+    }
+
+    /// Send `message` to the worker and wait for the result it answers with.
+    fn request(
+        &self,
+        message: impl FnOnce(std_mpsc::Sender<ExportResult>) -> Message,
+    ) -> TraceResult<()> {
+        let (result_sender, result_receiver) = std_mpsc::channel();
+        self.sender
+            .try_send(message(result_sender))
+            .map_err(|e| TraceError::from(e.to_string()))?;
+        result_receiver
+            .recv()
+            .map_err(|e| TraceError::Other(Box::new(e)))?
+    }
+}
+
+impl SpanProcessor for ByteBoundedProcessor {
+    fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        if !span.span_context.is_sampled() {
+            return;
+        }
+        if let Err(err) = self.sender.try_send(Message::Span(Box::new(span))) {
+            global::handle_error(TraceError::from(format!("error processing span: {err}")));
+        }
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.request(Message::Flush)
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        self.request(Message::Shutdown)
+    }
+}
+
+/// A user supplied processor registered ahead of the exporting one.
+#[derive(Debug)]
+pub(crate) struct BoxedProcessor(pub(crate) Box<dyn SpanProcessor>);
+
+impl SpanProcessor for BoxedProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.0.on_start(span, cx)
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.0.on_end(span)
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.0.force_flush()
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        self.0.shutdown()
+    }
+}
+
+/// Attribute the correlation id captured from the baggage is stored in until it's
+/// serialized as the record's `correlation_id`.
+pub(crate) const CORRELATION_ID_ATTRIBUTE: &str = "parseable.correlation_id";
+
+/// Copies a baggage entry of the context a span is started in onto the span, as baggage
+/// isn't part of the finished span handed to the exporter.
+#[derive(Debug)]
+pub(crate) struct CorrelationIdProcessor(pub(crate) Key);
+
+impl SpanProcessor for CorrelationIdProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if let Some(value) = cx.baggage().get(self.0.clone()) {
+            span.set_attribute(KeyValue::new(CORRELATION_ID_ATTRIBUTE, value.clone()));
+        }
+    }
+
+    fn on_end(&self, _span: SpanData) {}
+
+    fn force_flush(&self) -> TraceResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        Ok(())
+    }
+}
+
+async fn export<E: SpanExporter>(
+    exporter: &mut E,
+    buffer: &mut Vec<SpanData>,
+    buffered_bytes: &mut usize,
+) -> ExportResult {
+    if buffer.is_empty() {
+        return Ok(());
+    }
+    *buffered_bytes = 0;
+    exporter.export(std::mem::take(buffer)).await
+}
+
+/// Wait for `delay`, discarding its output, which the runtime doesn't require to be `Send`.
+fn elapsed<D: Future + Unpin>(delay: &mut D) -> impl Future<Output = ()> + '_ {
+    poll_fn(move |cx| Pin::new(&mut *delay).poll(cx).map(drop))
+}
+
+fn report(result: ExportResult) {
+    if let Err(err) = result {
+        global::handle_error(err);
+    }
+}
+
+/// Rough size of the records a span is serialized to. Every event is a record of its own
+/// repeating the span's fields.
+fn estimated_size(span: &SpanData) -> usize {
+    fn attributes<'a>(attributes: impl Iterator<Item = (&'a Key, &'a Value)>) -> usize {
+        attributes
+            .map(|(key, value)| key.as_str().len() + value.as_str().len() + 4)
+            .sum()
+    }
+
+    let record = RECORD_OVERHEAD
+        + span.name.len()
+        + attributes(span.resource.iter())
+        + attributes(span.attributes.iter());
+    let events: usize = span
+        .events
+        .iter()
+        .map(|event| {
+            record
+                + event.name.len()
+                + attributes(event.attributes.iter().map(|kv| (&kv.key, &kv.value)))
+        })
+        .sum();
+    record + events
+}
@@ -0,0 +1,111 @@
+use opentelemetry::{
+    sdk::{resource::ResourceDetector, Resource},
+    Key, KeyValue,
+};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    env,
+    fmt::Debug,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+/// Resources detected by [`CachedDetector`]s, by detector type and configuration.
+type DetectedResources = HashMap<(TypeId, String), Arc<OnceLock<Resource>>>;
+
+static DETECTED: OnceLock<Mutex<DetectedResources>> = OnceLock::new();
+
+/// Resource detector that runs the wrapped detector once per process and reuses its
+/// result afterwards, for detectors whose lookups are slow, e.g. querying a cloud
+/// metadata endpoint.
+///
+/// The cache lives as long as the process. Detectors of the same type and configuration,
+/// as told by their `Debug` output, share it, so applications building several
+/// exporters, or rebuilding one, only pay for detection the first time, even with a new
+/// `CachedDetector` each time.
+#[derive(Debug, Clone)]
+pub struct CachedDetector<D> {
+    detector: D,
+    detected: Arc<OnceLock<Resource>>,
+}
+
+impl<D: Debug + 'static> CachedDetector<D> {
+    pub fn new(detector: D) -> Self {
+        let key = (TypeId::of::<D>(), format!("{detector:?}"));
+        let detected = DETECTED
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .clone();
+        CachedDetector { detector, detected }
+    }
+}
+
+impl<D: ResourceDetector> ResourceDetector for CachedDetector<D> {
+    fn detect(&self, timeout: Duration) -> Resource {
+        self.detected
+            .get_or_init(|| self.detector.detect(timeout))
+            .clone()
+    }
+}
+
+/// Resource detector reading attribute values from environment variables, so each team
+/// can point e.g. `build_number` at the variable their CI sets. Variables that are unset
+/// or not valid unicode are skipped.
+///
+/// By default `vhost`, `build_number` and `build_date_time` are read from `Q_VHOST`,
+/// `BUILD_NUMBER` and `BUILD_DATE_TIME`.
+#[derive(Debug, Clone)]
+pub struct EnvVarDetector {
+    vars: Vec<(Key, String)>,
+}
+
+impl Default for EnvVarDetector {
+    fn default() -> Self {
+        EnvVarDetector {
+            vars: vec![
+                (Key::from_static_str("vhost"), "Q_VHOST".into()),
+                (Key::from_static_str("build_number"), "BUILD_NUMBER".into()),
+                (
+                    Key::from_static_str("build_date_time"),
+                    "BUILD_DATE_TIME".into(),
+                ),
+            ],
+        }
+    }
+}
+
+impl EnvVarDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the attribute `key` from the environment variable `var`, replacing the
+    /// variable `key` was read from so far.
+    pub fn with_var<K: Into<Key>, V: Into<String>>(mut self, key: K, var: V) -> Self {
+        let key = key.into();
+        self.vars.retain(|(existing, _)| *existing != key);
+        self.vars.push((key, var.into()));
+        self
+    }
+
+    /// Stop detecting the attribute `key`.
+    pub fn without<K: Into<Key>>(mut self, key: K) -> Self {
+        let key = key.into();
+        self.vars.retain(|(existing, _)| *existing != key);
+        self
+    }
+}
+
+impl ResourceDetector for EnvVarDetector {
+    fn detect(&self, _timeout: Duration) -> Resource {
+        Resource::new(self.vars.iter().filter_map(|(key, var)| {
+            env::var(var)
+                .ok()
+                .map(|value| KeyValue::new(key.clone(), value))
+        }))
+    }
+}
@@ -0,0 +1,48 @@
+use opentelemetry::{sdk::export::trace::SpanData, Key};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+type RouteFn = dyn Fn(&SpanData) -> Option<String> + Send + Sync;
+
+/// User supplied function picking the stream of a span, if it overrides the others.
+#[derive(Clone)]
+pub(crate) struct StreamRouter(pub(crate) Arc<RouteFn>);
+
+impl fmt::Debug for StreamRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StreamRouter")
+    }
+}
+
+/// Streams spans are routed to by the module path of the code that created them.
+#[derive(Debug, Clone)]
+pub(crate) struct TargetStreams {
+    /// Target prefixes and their streams, longest prefix first.
+    routes: Vec<(String, String)>,
+}
+
+impl TargetStreams {
+    pub(crate) fn new(routes: HashMap<String, String>) -> Self {
+        let mut routes: Vec<_> = routes.into_iter().collect();
+        routes.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        TargetStreams { routes }
+    }
+
+    /// Stream of the longest target prefix matching the span's `code.namespace`, which
+    /// tracing-opentelemetry records from the module path of the span. A prefix matches
+    /// whole path segments only: `myapp::db` matches `myapp::db::pool` but not
+    /// `myapp::dbx`.
+    pub(crate) fn stream_for(&self, span: &SpanData) -> Option<&str> {
+        let namespace = span
+            .attributes
+            .get(&Key::from_static_str("code.namespace"))?
+            .as_str();
+        self.routes
+            .iter()
+            .find(|(target, _)| {
+                namespace
+                    .strip_prefix(target.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map(|(_, stream)| stream.as_str())
+    }
+}
@@ -0,0 +1,50 @@
+//! The Tokio runtime requests, retry backoff and the exporter's background tasks run on.
+//!
+//! reqwest and the backoff's timers need Tokio. Within a Tokio runtime the exporter uses
+//! it; with the `async-std` feature it falls back to a runtime of its own, one worker
+//! thread started on first use, so spans can be batched on async-std.
+
+use std::future::Future;
+
+use tokio::runtime::Handle;
+
+/// Handle of the runtime to spawn the exporter's tasks on, if there is one.
+pub(crate) fn handle() -> Option<Handle> {
+    match Handle::try_current() {
+        Ok(handle) => Some(handle),
+        #[cfg(feature = "async-std")]
+        Err(_) => Some(fallback().handle().clone()),
+        #[cfg(not(feature = "async-std"))]
+        Err(_) => None,
+    }
+}
+
+/// Run `future` on the fallback runtime when it isn't polled within a Tokio runtime.
+pub(crate) async fn on_tokio<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "async-std")]
+    if Handle::try_current().is_err() {
+        // The fallback runtime is never shut down, so the task can only fail by panicking.
+        return match fallback().spawn(future).await {
+            Ok(output) => output,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+    }
+    future.await
+}
+
+#[cfg(feature = "async-std")]
+fn fallback() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("opentelemetry-parseable")
+            .enable_all()
+            .build()
+            .expect("failed to start the exporter's Tokio runtime")
+    })
+}
@@ -0,0 +1,102 @@
+use http::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    HeaderMap, HeaderValue,
+};
+use opentelemetry::{global, trace::TraceError};
+use reqwest::Url;
+use serde::Serialize;
+use tokio::sync::OnceCell;
+
+/// A column of the static schema, using Parseable's schema data type names.
+#[derive(Serialize, Debug)]
+struct SchemaField {
+    name: &'static str,
+    data_type: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+struct StaticSchema {
+    fields: Vec<SchemaField>,
+}
+
+/// Publishes the static schema of a stream once, ahead of the first export to it.
+///
+/// The schema is sent with Parseable's stream creation API
+/// (`PUT /api/v1/logstream/{stream}` with `X-P-Static-Schema-Flag: true`). Servers
+/// without static schema support, or where the stream already exists, reject the
+/// request; the failure is reported through the global error handler and exporting
+/// carries on with schema inference.
+#[derive(Debug)]
+pub(crate) struct SchemaPublisher {
+    stream_url: Url,
+    headers: HeaderMap,
+    schema: StaticSchema,
+    published: OnceCell<()>,
+}
+
+impl SchemaPublisher {
+    pub(crate) fn new(
+        ingest_url: &Url,
+        stream: &str,
+        request_headers: &HeaderMap,
+        fields: Vec<(&'static str, &'static str)>,
+    ) -> Result<Self, TraceError> {
+        // The stream API sits next to the ingest one, below the API root.
+        let mut stream_url = ingest_url.clone();
+        stream_url
+            .path_segments_mut()
+            .map_err(|()| TraceError::from(format!("invalid Parseable endpoint '{ingest_url}'")))?
+            .pop_if_empty()
+            .pop()
+            .extend(["logstream", stream]);
+
+        let mut headers = HeaderMap::new();
+        if let Some(auth) = request_headers.get(AUTHORIZATION) {
+            headers.insert(AUTHORIZATION, auth.clone());
+        }
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert("X-P-Static-Schema-Flag", HeaderValue::from_static("true"));
+
+        let fields = fields
+            .into_iter()
+            .map(|(name, data_type)| SchemaField { name, data_type })
+            .collect();
+
+        Ok(SchemaPublisher {
+            stream_url,
+            headers,
+            schema: StaticSchema { fields },
+            published: OnceCell::new(),
+        })
+    }
+
+    /// Publish the schema unless an earlier export already attempted it.
+    pub(crate) async fn ensure_published(&self, client: &reqwest::Client) {
+        self.published
+            .get_or_init(|| async {
+                if let Err(err) = self.publish(client).await {
+                    global::handle_error(err);
+                }
+            })
+            .await;
+    }
+
+    async fn publish(&self, client: &reqwest::Client) -> Result<(), TraceError> {
+        let response = client
+            .put(self.stream_url.clone())
+            .headers(self.headers.clone())
+            .json(&self.schema)
+            .send()
+            .await
+            .map_err(|e| TraceError::Other(Box::new(e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TraceError::from(format!(
+                "Parseable did not accept the schema published to {}: {}",
+                self.stream_url, status
+            )));
+        }
+        Ok(())
+    }
+}
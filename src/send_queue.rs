@@ -0,0 +1,42 @@
+use crate::in_flight::InFlightGuard;
+use opentelemetry::trace::TraceError;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+/// A serialized batch waiting for the send queue's task to send it.
+pub(crate) struct QueuedRequest {
+    pub(crate) request: Result<reqwest::Request, TraceError>,
+    pub(crate) message_count: usize,
+    /// Stream the request is sent to, for reporting failures.
+    pub(crate) stream: String,
+    /// Keeps the export in flight until its request has completed.
+    pub(crate) in_flight: InFlightGuard,
+}
+
+/// Bounded queue between `export` and the task sending the requests, so an export
+/// returns as soon as its batch is queued.
+#[derive(Debug)]
+pub(crate) struct SendQueue {
+    sender: mpsc::Sender<QueuedRequest>,
+    /// Handed to the sending task when the first batch is queued.
+    receiver: Mutex<Option<mpsc::Receiver<QueuedRequest>>>,
+}
+
+impl SendQueue {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        SendQueue {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub(crate) fn sender(&self) -> mpsc::Sender<QueuedRequest> {
+        self.sender.clone()
+    }
+
+    /// The receiving end, unless the sending task has been spawned already.
+    pub(crate) fn take_receiver(&self) -> Option<mpsc::Receiver<QueuedRequest>> {
+        self.receiver.lock().unwrap().take()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{BatchSettings, Compression};
+use opentelemetry::{
+    sdk::trace::{Config, Tracer},
+    trace::{SamplingDecision, SamplingResult, Span, TraceState, Tracer as _},
+    KeyValue,
+};
+
+/// Name of the span marking that the exporter was installed.
+pub(crate) const STARTUP_MARKER: &str = "parseable exporter started";
+
+/// Attributes of the startup marker describing how the exporter was configured. The
+/// fingerprint changes whenever any of the other attributes does.
+pub(crate) fn marker_attributes(
+    config: &Config,
+    batch: Option<BatchSettings>,
+    byte_flush: Option<usize>,
+    compression: Compression,
+) -> Vec<KeyValue> {
+    let mut attributes = vec![
+        KeyValue::new("exporter.version", env!("CARGO_PKG_VERSION")),
+        KeyValue::new("exporter.sampler", format!("{:?}", config.sampler)),
+        KeyValue::new("exporter.compression", format!("{compression:?}")),
+    ];
+    if let Some(batch) = batch {
+        attributes.extend([
+            KeyValue::new("exporter.batch_size", batch.max_export_batch_size as i64),
+            KeyValue::new("exporter.queue_size", batch.max_queue_size as i64),
+            KeyValue::new(
+                "exporter.interval_ms",
+                batch.scheduled_delay.as_millis() as i64,
+            ),
+        ]);
+    }
+    if let Some(max_bytes) = byte_flush {
+        attributes.push(KeyValue::new("exporter.byte_flush", max_bytes as i64));
+    }
+
+    let mut fingerprint = FNV_OFFSET_BASIS;
+    for attribute in &attributes {
+        fingerprint = fnv1a(fingerprint, attribute.key.as_str().as_bytes());
+        fingerprint = fnv1a(fingerprint, attribute.value.as_str().as_bytes());
+    }
+    attributes.push(KeyValue::new(
+        "exporter.config_fingerprint",
+        format!("{fingerprint:016x}"),
+    ));
+    attributes
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Continue the 64-bit FNV-1a hash `hash` with `bytes` and a terminating zero byte, so
+/// the fingerprint is stable across Rust releases and attributes can't run together.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().chain([&0]).fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Record the startup marker span. It is always sampled, so the configured sampler
+/// can't hide it.
+pub(crate) fn emit_marker(tracer: &Tracer, attributes: Vec<KeyValue>) {
+    let mut span = tracer
+        .span_builder(STARTUP_MARKER)
+        .with_attributes(attributes)
+        .with_sampling_result(SamplingResult {
+            decision: SamplingDecision::RecordAndSample,
+            attributes: Vec::new(),
+            trace_state: TraceState::default(),
+        })
+        .start(tracer);
+    span.end();
+}
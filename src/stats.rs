@@ -0,0 +1,123 @@
+use crate::Clock;
+use chrono::{DateTime, Utc};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// Snapshot of an exporter's totals, returned by
+/// [`ParseableExporter::stats`](crate::ParseableExporter::stats).
+///
+/// Records are the flattened rows sent to Parseable: one per span without events, and
+/// one per event otherwise. Failed exports include batches dropped before sending, e.g.
+/// beyond the buffered batches limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExporterStats {
+    pub batches_exported: u64,
+    pub records_exported: u64,
+    pub batches_failed: u64,
+    pub records_failed: u64,
+    /// Time of the last successful export, if there was one.
+    pub last_success: Option<SystemTime>,
+}
+
+/// Running totals of the exporter's exports, logged periodically when health logging is
+/// enabled.
+#[derive(Debug)]
+pub(crate) struct ExportStats {
+    clock: Arc<dyn Clock>,
+    batches_exported: AtomicU64,
+    records_exported: AtomicU64,
+    batches_failed: AtomicU64,
+    records_failed: AtomicU64,
+    last_success: Mutex<Option<SystemTime>>,
+    /// Interval of the health log, if enabled.
+    health_logging: Option<Duration>,
+    health_logging_started: AtomicBool,
+}
+
+impl ExportStats {
+    pub(crate) fn new(clock: Arc<dyn Clock>, health_logging: Option<Duration>) -> Self {
+        ExportStats {
+            clock,
+            batches_exported: AtomicU64::new(0),
+            records_exported: AtomicU64::new(0),
+            batches_failed: AtomicU64::new(0),
+            records_failed: AtomicU64::new(0),
+            last_success: Mutex::new(None),
+            health_logging,
+            health_logging_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Count an export of `records` records, successful or failed.
+    pub(crate) fn record(&self, succeeded: bool, records: usize) {
+        let (batches, total) = if succeeded {
+            *self.last_success.lock().unwrap() = Some(self.clock.now());
+            (&self.batches_exported, &self.records_exported)
+        } else {
+            (&self.batches_failed, &self.records_failed)
+        };
+        batches.fetch_add(1, Ordering::Relaxed);
+        total.fetch_add(records as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> ExporterStats {
+        ExporterStats {
+            batches_exported: self.batches_exported.load(Ordering::Relaxed),
+            records_exported: self.records_exported.load(Ordering::Relaxed),
+            batches_failed: self.batches_failed.load(Ordering::Relaxed),
+            records_failed: self.records_failed.load(Ordering::Relaxed),
+            last_success: *self.last_success.lock().unwrap(),
+        }
+    }
+
+    /// Spawn the task logging the stats of `stream` every health logging interval, on the
+    /// current Tokio runtime, unless it is disabled or running already. The task stops
+    /// once the exporter is dropped.
+    pub(crate) fn start_health_logging(self: &Arc<Self>, stream: impl FnOnce() -> String) {
+        let Some(period) = self.health_logging else {
+            return;
+        };
+        let Some(runtime) = crate::runtime::handle() else {
+            return;
+        };
+        if self.health_logging_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let stats = Arc::downgrade(self);
+        let stream = stream();
+        runtime.spawn(async move {
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                match stats.upgrade() {
+                    Some(stats) => stats.log_health(&stream),
+                    None => break,
+                }
+            }
+        });
+    }
+
+    fn log_health(&self, stream: &str) {
+        let last_success = self
+            .last_success
+            .lock()
+            .unwrap()
+            .map(|time| DateTime::<Utc>::from(time).to_rfc3339())
+            .unwrap_or_else(|| "never".into());
+        tracing::info!(
+            stream,
+            batches_exported = self.batches_exported.load(Ordering::Relaxed),
+            records_exported = self.records_exported.load(Ordering::Relaxed),
+            batches_failed = self.batches_failed.load(Ordering::Relaxed),
+            records_failed = self.records_failed.load(Ordering::Relaxed),
+            %last_success,
+            "Parseable exporter health"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "async-std")]
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::sdk::trace;
+use opentelemetry::trace::{Span, Tracer};
+use opentelemetry::{global, runtime};
+
+mod support;
+
+#[async_std::test]
+async fn exports_are_sent_without_a_tokio_runtime() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "span");
+}
+
+#[async_std::test]
+async fn spans_are_batched_on_async_std() {
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_send_queue(4)
+        .install_batch(runtime::AsyncStd, trace::config())
+        .unwrap();
+
+    tracer.start("batched").end();
+    // Shutting down flushes the batch and waits for the queued request to be sent.
+    async_std::task::spawn_blocking(global::shutdown_tracer_provider).await;
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "batched");
+}
@@ -0,0 +1,36 @@
+use std::env;
+use std::time::Duration;
+
+use opentelemetry_parseable::{BatchSettings, Signal};
+
+// Environment variables are process wide, so every case runs inside one test.
+#[test]
+fn signal_specific_env_vars_override_generic_ones() {
+    let defaults = BatchSettings::from_env(Signal::Traces);
+    assert_eq!(defaults.max_queue_size, 65536);
+    assert_eq!(defaults.max_export_batch_size, 8192);
+    assert_eq!(defaults.scheduled_delay, Duration::from_millis(1000));
+
+    env::set_var("OTLP_BATCH_SIZE", "100");
+    env::set_var("OTLP_INTERVAL_MILLIS", "500");
+    env::set_var("OTLP_METRICS_INTERVAL_MILLIS", "60000");
+    env::set_var("OTLP_LOGS_BATCH_SIZE", "10");
+
+    let traces = BatchSettings::from_env(Signal::Traces);
+    assert_eq!(traces.max_export_batch_size, 100);
+    assert_eq!(traces.scheduled_delay, Duration::from_millis(500));
+
+    let logs = BatchSettings::from_env(Signal::Logs);
+    assert_eq!(logs.max_export_batch_size, 10);
+    assert_eq!(logs.scheduled_delay, Duration::from_millis(500));
+
+    let metrics = BatchSettings::from_env(Signal::Metrics);
+    assert_eq!(metrics.max_export_batch_size, 100);
+    assert_eq!(metrics.scheduled_delay, Duration::from_millis(60000));
+
+    env::set_var("OTLP_TRACES_BATCH_SIZE", "not a number");
+    assert_eq!(
+        BatchSettings::from_env(Signal::Traces).max_export_batch_size,
+        100
+    );
+}
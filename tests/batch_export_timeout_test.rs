@@ -0,0 +1,87 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::future::BoxFuture;
+use futures_core::Stream;
+use opentelemetry::runtime::{self, Runtime};
+use opentelemetry::sdk::trace::{self, TraceRuntime};
+use opentelemetry::trace::Tracer;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+/// Tokio, except that the batch processor's interval first ticks after a full period
+/// instead of right away, so nothing but the flush exports the span.
+#[derive(Debug, Clone)]
+struct DeferredTicks;
+
+/// Ticks of a Tokio interval as a stream.
+struct Ticks(tokio::time::Interval);
+
+impl Stream for Ticks {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        self.get_mut().0.poll_tick(cx).map(|_| Some(()))
+    }
+}
+
+impl Runtime for DeferredTicks {
+    type Interval = Ticks;
+    type Delay = <runtime::Tokio as Runtime>::Delay;
+
+    fn interval(&self, period: Duration) -> Ticks {
+        Ticks(tokio::time::interval_at(
+            tokio::time::Instant::now() + period,
+            period,
+        ))
+    }
+
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        runtime::Tokio.spawn(future)
+    }
+
+    fn delay(&self, duration: Duration) -> Self::Delay {
+        runtime::Tokio.delay(duration)
+    }
+}
+
+impl TraceRuntime for DeferredTicks {
+    type Receiver = <runtime::Tokio as TraceRuntime>::Receiver;
+    type Sender = <runtime::Tokio as TraceRuntime>::Sender;
+
+    fn batch_message_channel(&self, capacity: usize) -> (Self::Sender, Self::Receiver) {
+        runtime::Tokio.batch_message_channel(capacity)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn batch_exports_time_out_after_the_configured_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+        .mount(&server)
+        .await;
+    let tracer = support::builder_for(&server)
+        .with_batch_export_timeout(Duration::from_millis(200))
+        .with_scheduled_delay(Duration::from_secs(60))
+        .with_shutdown_grace_period(Duration::from_millis(100))
+        .install_batch(DeferredTicks, trace::config())
+        .unwrap();
+
+    drop(tracer.start("slow"));
+    let provider = tracer.provider().unwrap();
+    let started = Instant::now();
+    let flushed = tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(flushed.iter().any(Result::is_err), "{flushed:?}");
+    tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider)
+        .await
+        .unwrap();
+}
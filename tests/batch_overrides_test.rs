@@ -0,0 +1,41 @@
+use std::env;
+use std::time::Duration;
+
+use opentelemetry::trace::Tracer;
+use opentelemetry::{runtime, sdk::trace};
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn builder_batch_settings_take_precedence_over_the_environment() {
+    env::set_var("OTLP_TRACES_BATCH_SIZE", "1000");
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_max_queue_size(64)
+        .with_max_export_batch_size(2)
+        .with_scheduled_delay(Duration::from_secs(3600))
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+
+    for _ in 0..5 {
+        tracer.in_span("batched", |_| {});
+    }
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let sizes: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            serde_json::from_slice::<Vec<serde_json::Value>>(&request.body)
+                .unwrap()
+                .len()
+        })
+        .collect();
+    assert_eq!(sizes.iter().sum::<usize>(), 5);
+    assert!(sizes.iter().all(|&size| size <= 2), "{sizes:?}");
+}
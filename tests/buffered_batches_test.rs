@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn batches_beyond_the_limit_are_dropped() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_max_buffered_batches(1)
+        .build_exporter()
+        .unwrap();
+
+    let first = tokio::spawn(exporter.export(vec![support::span_data("first")]));
+    let dropped = exporter.export(vec![support::span_data("dropped")]).await;
+    assert!(dropped.is_err());
+    first.await.unwrap().unwrap();
+
+    exporter
+        .export(vec![support::span_data("after")])
+        .await
+        .unwrap();
+    let names: Vec<String> = support::received_records(&server)
+        .await
+        .iter()
+        .map(|r| r["span_name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["first", "after"]);
+}
@@ -0,0 +1,368 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::{Auth, ParseableApiVersion};
+use reqwest::redirect::Policy;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+mod support;
+
+async fn redirecting_parseable() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(307).insert_header("Location", "/moved/ingest"))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn redirects_are_not_followed_by_default() {
+    let server = redirecting_parseable().await;
+    Mock::given(path("/moved/ingest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let result = exporter.export(vec![support::span_data("span")]).await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("307"), "{error}");
+    assert!(error.contains("/moved/ingest"), "{error}");
+}
+
+#[tokio::test]
+async fn redirects_follow_the_configured_policy() {
+    let server = redirecting_parseable().await;
+    Mock::given(path("/moved/ingest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_redirect_policy(Policy::limited(2))
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+}
+
+#[test]
+fn an_invalid_endpoint_is_named_in_the_error() {
+    let error = opentelemetry_parseable::ParseableExporterBuilder::default()
+        .with_host("parseable internal")
+        .build_exporter()
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("invalid Parseable endpoint"), "{error}");
+    assert!(error.contains("parseable internal"), "{error}");
+}
+
+#[test]
+fn an_invalid_stream_header_is_named_in_the_error() {
+    let error = opentelemetry_parseable::ParseableExporterBuilder::default()
+        .with_service_name("my\nservice")
+        .build_exporter()
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("invalid X-P-Stream header"), "{error}");
+}
+
+async fn authorization_sent_with(auth: Auth) -> Option<String> {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_auth(auth)
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    support::header(&requests[0], "authorization")
+}
+
+#[tokio::test]
+async fn bearer_tokens_replace_basic_auth() {
+    let authorization = authorization_sent_with(Auth::Bearer("s3cr3t".into())).await;
+    assert_eq!(authorization.as_deref(), Some("Bearer s3cr3t"));
+}
+
+#[tokio::test]
+async fn no_authorization_is_sent_without_auth() {
+    assert_eq!(authorization_sent_with(Auth::None).await, None);
+}
+
+#[tokio::test]
+async fn basic_auth_encodes_username_and_password() {
+    let authorization = authorization_sent_with(Auth::Basic {
+        username: "user".into(),
+        password: "pass".into(),
+    })
+    .await;
+    assert_eq!(authorization.as_deref(), Some("Basic dXNlcjpwYXNz"));
+}
+
+#[tokio::test]
+async fn requests_time_out_after_the_configured_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+        .mount(&server)
+        .await;
+    let exporter = support::builder_for(&server)
+        .with_timeout(Duration::from_millis(200))
+        .with_retry(0, Duration::ZERO)
+        .build_exporter()
+        .unwrap();
+
+    let started = Instant::now();
+    let error = exporter
+        .export_now(vec![support::span_data("span")])
+        .await
+        .unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(error.status, None);
+}
+
+#[tokio::test]
+async fn requests_go_to_the_configured_base_path() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/telemetry/api/v1/ingest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_base_path("/telemetry/")
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn requests_go_to_the_configured_api_version() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v2/ingest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_api_version(ParseableApiVersion::V2)
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn requests_go_to_the_configured_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/telemetry/api/v1/ingest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&server)
+        .await;
+    for endpoint in ["telemetry", "telemetry/api/v1/ingest"] {
+        let endpoint = format!("{}/{endpoint}", server.uri()).parse().unwrap();
+        let mut exporter = opentelemetry_parseable::ParseableExporterBuilder::default()
+            .with_host("")
+            .with_endpoint(endpoint)
+            .with_service_name("test-service")
+            .build_exporter()
+            .unwrap();
+
+        exporter
+            .export(vec![support::span_data("span")])
+            .await
+            .unwrap();
+    }
+}
+
+#[test]
+fn invalid_settings_are_named_in_the_error() {
+    let builder = opentelemetry_parseable::ParseableExporterBuilder::default;
+    for (builder, expected) in [
+        (builder().with_host(""), "host must not be empty"),
+        (builder().with_port("http"), "port must be a number"),
+        (
+            builder().with_service_name(""),
+            "service name must not be empty",
+        ),
+    ] {
+        let error = builder.build_exporter().unwrap_err().to_string();
+        assert!(error.contains(expected), "{error}");
+    }
+}
+
+#[test]
+fn invalid_tls_material_is_named_in_the_error() {
+    let builder = opentelemetry_parseable::ParseableExporterBuilder::default;
+    for (builder, expected) in [
+        (
+            builder().with_client_identity(b"not a cert".to_vec(), b"not a key".to_vec()),
+            "invalid client identity",
+        ),
+        (
+            builder().with_root_ca(b"not a cert".to_vec()),
+            "invalid root CA certificate",
+        ),
+    ] {
+        let error = builder.build_exporter().unwrap_err().to_string();
+        assert!(error.contains(expected), "{error}");
+    }
+}
+
+/// Records when each request arrives and answers it after `delay`.
+struct DelayedResponder {
+    arrivals: Arc<Mutex<Vec<Instant>>>,
+    delay: Duration,
+}
+
+impl Respond for DelayedResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        self.arrivals.lock().unwrap().push(Instant::now());
+        ResponseTemplate::new(200).set_delay(self.delay)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_wait_for_a_free_connection_slot() {
+    let server = MockServer::start().await;
+    let arrivals = Arc::new(Mutex::new(Vec::new()));
+    let delay = Duration::from_millis(200);
+    Mock::given(method("POST"))
+        .respond_with(DelayedResponder {
+            arrivals: arrivals.clone(),
+            delay,
+        })
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_max_connections_per_host(1)
+        .build_exporter()
+        .unwrap();
+
+    let exports =
+        ["first", "second", "third"].map(|name| exporter.export(vec![support::span_data(name)]));
+    let [first, second, third] = exports;
+    let (first, second, third) = tokio::join!(first, second, third);
+    first.and(second).and(third).unwrap();
+
+    // Each request is only sent once the previous response arrived, so no two requests
+    // were ever in flight together.
+    let arrivals = arrivals.lock().unwrap();
+    assert_eq!(arrivals.len(), 3);
+    for pair in arrivals.windows(2) {
+        assert!(pair[1] - pair[0] >= delay, "{:?}", pair[1] - pair[0]);
+    }
+}
+
+#[tokio::test]
+async fn exports_succeed_with_a_tuned_connection_pool() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_pool_max_idle_per_host(1)
+        .with_pool_idle_timeout(Duration::from_millis(10))
+        .build_exporter()
+        .unwrap();
+
+    for name in ["first", "second"] {
+        exporter
+            .export(vec![support::span_data(name)])
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(support::received_records(&server).await.len(), 2);
+}
+
+#[tokio::test]
+async fn requests_go_through_the_configured_proxy() {
+    let proxy = support::mock_parseable().await;
+    let mut exporter = opentelemetry_parseable::ParseableExporterBuilder::default()
+        .with_host("parseable.invalid")
+        .with_proxy(reqwest::Proxy::http(proxy.uri()).unwrap())
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("proxied")])
+        .await
+        .unwrap();
+
+    let requests = proxy.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.host_str(), Some("parseable.invalid"));
+}
+
+#[tokio::test]
+async fn dynamic_headers_are_produced_for_every_request() {
+    let server = support::mock_parseable().await;
+    let signatures = Arc::new(AtomicUsize::new(0));
+    let counter = signatures.clone();
+    let mut tags = http::HeaderMap::new();
+    tags.insert("X-P-Tag-team", "payments".parse().unwrap());
+    let mut exporter = support::builder_for(&server)
+        .with_tags(tags)
+        .with_dynamic_headers(move || {
+            let signature = counter.fetch_add(1, Ordering::SeqCst);
+            let mut headers = http::HeaderMap::new();
+            headers.insert("X-Signature", signature.to_string().parse().unwrap());
+            headers.insert("X-P-Stream", "hijacked".parse().unwrap());
+            headers
+        })
+        .build_exporter()
+        .unwrap();
+
+    for name in ["first", "second"] {
+        exporter
+            .export(vec![support::span_data(name)])
+            .await
+            .unwrap();
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    let signatures: Vec<_> = requests
+        .iter()
+        .map(|request| support::header(request, "X-Signature"))
+        .collect();
+    assert_eq!(signatures, [Some("0".to_string()), Some("1".to_string())]);
+    for request in &requests {
+        assert_eq!(
+            support::header(request, "X-P-Tag-team").as_deref(),
+            Some("payments")
+        );
+        assert_eq!(
+            support::header(request, "X-P-Stream").as_deref(),
+            Some("test-service")
+        );
+    }
+}
@@ -1,28 +1,32 @@
+use opentelemetry::sdk::resource::ResourceDetector;
+use opentelemetry::sdk::trace::{self, Sampler, TraceRuntime};
 use opentelemetry::sdk::Resource;
-use opentelemetry::KeyValue;
-use opentelemetry::{runtime, sdk::trace};
+use opentelemetry::{global, runtime};
+use opentelemetry::{Key, KeyValue};
 use std::env;
-use tokio::time::{sleep, Duration};
+use std::sync::OnceLock;
+use std::time::Duration;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, EnvFilter};
+use tracing_subscriber::{filter::LevelFilter, fmt, layer::SubscriberExt, EnvFilter};
 
-use opentelemetry_parseable::ParseableExporterBuilder;
+use opentelemetry_parseable::{EnvVarDetector, ParseableExporterBuilder, ParseableLogLayer};
+
+/// Kept to flush the events logged outside of spans on shutdown.
+static LOG_LAYER: OnceLock<ParseableLogLayer> = OnceLock::new();
 
 fn get_resources(service: &str) -> Resource {
+    let detected = EnvVarDetector::default().detect(Duration::ZERO);
+    let detected_or = |key: &'static str, fallback: &str| {
+        detected
+            .get(Key::from_static_str(key))
+            .map_or_else(|| fallback.to_string(), |value| value.as_str().into_owned())
+    };
     let kvs = [
-        KeyValue::new(
-            "vhost",
-            std::env::var("Q_VHOST")
-                .unwrap_or("Not Set".into())
-                .replace('/', ""),
-        ),
-        KeyValue::new(
-            "build_number",
-            std::env::var("BUILD_NUMBER").unwrap_or("local build".into()),
-        ),
+        KeyValue::new("vhost", detected_or("vhost", "Not Set").replace('/', "")),
+        KeyValue::new("build_number", detected_or("build_number", "local build")),
         KeyValue::new(
             "build_date_time",
-            std::env::var("BUILD_DATE_TIME").unwrap_or("local build".into()),
+            detected_or("build_date_time", "local build"),
         ),
         KeyValue::new("user.real_name", whoami::realname()),
         KeyValue::new("user.user_name", whoami::username()),
@@ -43,43 +47,105 @@ fn get_resources(service: &str) -> Resource {
     Resource::new(kvs)
 }
 
-fn service_name() -> Option<String> {
+/// Used when neither the executable nor `OTEL_SERVICE_NAME` names the service.
+const DEFAULT_SERVICE_NAME: &str = "unknown-service";
+
+fn exe_service_name() -> Option<String> {
     env::current_exe()
         .ok()
         .as_ref()
         .map(std::path::Path::new)
         .and_then(std::path::Path::file_name)
         .and_then(std::ffi::OsStr::to_str)
-        .map(|s| {
-            // if the exe contains a '-', take only the preceding part
-            s.split_once('_').map(|(s, _)| s).unwrap_or(s).to_string()
-        })
+        .map(|s| service_name_from_exe(s).to_string())
+}
+
+/// The service name an executable's file name stands for: the part preceding the first
+/// `-`, which drops the hash cargo appends to test binaries (`integration_test-3f2a…`)
+/// and version suffixes (`api-1.2.3`). Underscores are kept.
+pub fn service_name_from_exe(file_name: &str) -> &str {
+    file_name
+        .split_once('-')
+        .map_or(file_name, |(name, _)| name)
+}
+
+/// The service named by the executable, falling back to `OTEL_SERVICE_NAME` and then
+/// to [`DEFAULT_SERVICE_NAME`], so telemetry startup never fails for lack of a name.
+fn service_name() -> String {
+    exe_service_name()
+        .filter(|name| !name.is_empty())
+        .or_else(|| env::var("OTEL_SERVICE_NAME").ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string())
 }
 
+/// How [`telemetry_startup`] writes events to stdout.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StdoutFormat {
+    /// One plain line per event, without ANSI colors.
+    #[default]
+    Plain,
+    /// One JSON object per line, e.g. for containers shipping stdout to a log collector.
+    Json,
+    /// Multi-line, colored output for reading locally.
+    Pretty,
+    /// Nothing is written to stdout.
+    None,
+}
+
+/// Install the Parseable exporter and log layer as the global subscriber, along with
+/// a stdout layer in the given format. Spans are sampled with `sampler`, e.g.
+/// `Sampler::TraceIdRatioBased(0.1)`, or else with the sampler the `OTEL_TRACES_SAMPLER`
+/// and `OTEL_TRACES_SAMPLER_ARG` environment variables select, all spans by default.
 #[inline]
-pub async fn telemetry_startup() {
-    let service_name = service_name().expect("Unable to get service name");
+pub async fn telemetry_startup(sampler: Option<Sampler>, stdout: StdoutFormat) {
+    install(runtime::Tokio, sampler, stdout);
+}
+
+/// Like [`telemetry_startup`], batching spans on async-std. The exporter sends them on
+/// a Tokio runtime of its own.
+#[cfg(feature = "async-std")]
+#[allow(dead_code)]
+pub async fn telemetry_startup_async_std(sampler: Option<Sampler>, stdout: StdoutFormat) {
+    install(runtime::AsyncStd, sampler, stdout);
+}
+
+fn install<R: TraceRuntime>(runtime: R, sampler: Option<Sampler>, stdout: StdoutFormat) {
+    let service_name = service_name();
     if std::env::var("RUST_LOG").ok().is_none() {
         std::env::set_var("RUST_LOG", "info");
     }
 
     // parseable exporter
-    let config = trace::config().with_resource(get_resources(&service_name));
+    let mut config = trace::config().with_resource(get_resources(&service_name));
+    if let Some(sampler) = sampler {
+        config = config.with_sampler(sampler);
+    }
     let tracer = ParseableExporterBuilder::default()
         .with_service_name(&service_name)
-        .install_batch(runtime::Tokio, config)
+        .install_batch(runtime, config)
         .expect("Unable to build parseable exporter");
+    let log_layer = ParseableExporterBuilder::default()
+        .with_service_name(&service_name)
+        .build_log_layer()
+        .expect("Unable to build parseable log layer");
+    let _ = LOG_LAYER.set(log_layer.clone());
 
-    let collector = tracing_subscriber::registry().with(
-        tracing_subscriber::fmt::layer()
-            .with_ansi(false)
-            .with_filter(EnvFilter::from_default_env()),
-    );
+    let stdout_layer = match stdout {
+        StdoutFormat::Plain => Some(fmt::layer().with_ansi(false).boxed()),
+        StdoutFormat::Json => Some(fmt::layer().json().boxed()),
+        StdoutFormat::Pretty => Some(fmt::layer().pretty().boxed()),
+        StdoutFormat::None => None,
+    };
+    let collector = tracing_subscriber::registry()
+        .with(stdout_layer.with_filter(EnvFilter::from_default_env()));
     let collector = collector.with(
         tracing_opentelemetry::layer()
             .with_tracer(tracer)
             .with_filter(LevelFilter::INFO),
     );
+    let collector = collector.with(log_layer.with_filter(LevelFilter::INFO));
     if tracing::subscriber::set_global_default(collector).is_err() {
         eprintln!(
             "Error setting tracing subscriber, probably another subscriber has already been set?"
@@ -89,5 +155,16 @@ pub async fn telemetry_startup() {
 
 #[inline]
 pub async fn telemetry_shutdown() {
-    sleep(Duration::from_secs(2)).await;
+    if let Some(log_layer) = LOG_LAYER.get() {
+        log_layer.flush().await;
+    }
+    // Shutting down flushes the batch and waits for in-flight exports, blocking the
+    // calling thread, so it runs off the runtime the exports are driven by. A thread of
+    // its own works with either runtime.
+    let (shut_down, done) = tokio::sync::oneshot::channel();
+    std::thread::spawn(|| {
+        global::shutdown_tracer_provider();
+        let _ = shut_down.send(());
+    });
+    done.await.expect("Unable to shut down tracer provider");
 }
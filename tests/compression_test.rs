@@ -0,0 +1,50 @@
+#![cfg(any(feature = "zstd", feature = "brotli"))]
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::KeyValue;
+use opentelemetry_parseable::Compression;
+
+mod support;
+
+/// Export a span large enough to be compressed and return the encoded request body.
+async fn export_compressed(compression: Compression, encoding: &str) -> Vec<u8> {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_compression(compression)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("large");
+    span.attributes
+        .insert(KeyValue::new("payload", "x".repeat(4096)));
+    exporter.export(vec![span]).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        support::header(&requests[0], "content-encoding").as_deref(),
+        Some(encoding)
+    );
+    assert!(requests[0].body.len() < 4096);
+    requests[0].body.clone()
+}
+
+fn assert_round_trips(body: &[u8]) {
+    let records: serde_json::Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(records[0]["span_name"], "large");
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn zstd_round_trips() {
+    let body = export_compressed(Compression::Zstd, "zstd").await;
+    assert_round_trips(&zstd::stream::decode_all(body.as_slice()).unwrap());
+}
+
+#[cfg(feature = "brotli")]
+#[tokio::test]
+async fn brotli_round_trips() {
+    let body = export_compressed(Compression::Brotli, "br").await;
+    let mut decoded = Vec::new();
+    brotli::BrotliDecompress(&mut body.as_slice(), &mut decoded).unwrap();
+    assert_round_trips(&decoded);
+}
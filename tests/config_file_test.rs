@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::ParseableExporterBuilder;
+
+mod support;
+
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+// Environment variables are process wide, so the override is checked in the same test.
+#[tokio::test]
+async fn representative_config_configures_the_exporter() {
+    let server = support::mock_parseable().await;
+    let address = server.address();
+    let config = format!(
+        r#"
+host = "{}"
+port = {}
+username = "file-user"
+password = "file-password"
+stream = "from-file"
+
+[batch]
+batch_size = 512
+
+[export]
+exporter_id = true
+numeric_status = true
+resource_as_headers = true
+"#,
+        address.ip(),
+        address.port()
+    );
+    let toml = write_config("exporter.toml", &config);
+
+    let mut exporter = ParseableExporterBuilder::from_config_file(&toml)
+        .unwrap()
+        .build_exporter()
+        .unwrap();
+    exporter
+        .export(vec![support::span_data("configured")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        support::header(&requests[0], "x-p-stream").as_deref(),
+        Some("from-file")
+    );
+    // base64 of file-user:file-password
+    assert_eq!(
+        support::header(&requests[0], "authorization").as_deref(),
+        Some("Basic ZmlsZS11c2VyOmZpbGUtcGFzc3dvcmQ=")
+    );
+    // Without a config of its own, the SDK's default resource is sent.
+    assert!(support::header(&requests[0], "x-p-meta-service.name").is_some());
+    let records = support::received_records(&server).await;
+    assert!(records[0]["exporter_id"].is_string());
+    assert_eq!(records[0]["status_code"], 0);
+
+    std::env::set_var("PARSEABLE_USERNAME", "env-user");
+    let json = write_config(
+        "exporter.json",
+        &format!(
+            r#"{{"host": "{}", "port": {}, "username": "file-user", "password": "file-password"}}"#,
+            address.ip(),
+            address.port()
+        ),
+    );
+    let mut exporter = ParseableExporterBuilder::from_config_file(&json)
+        .unwrap()
+        .build_exporter()
+        .unwrap();
+    std::env::remove_var("PARSEABLE_USERNAME");
+    exporter
+        .export(vec![support::span_data("overridden")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    // base64 of env-user:file-password
+    assert_eq!(
+        support::header(&requests[1], "authorization").as_deref(),
+        Some("Basic ZW52LXVzZXI6ZmlsZS1wYXNzd29yZA==")
+    );
+}
+
+#[test]
+fn unknown_and_malformed_keys_are_rejected() {
+    let unknown = write_config("unknown.toml", "[export]\nexporter_idd = true\n");
+    let error = ParseableExporterBuilder::from_config_file(&unknown)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.contains("exporter_idd"), "{error}");
+
+    let malformed = write_config("malformed.json", r#"{"port": "not a port"}"#);
+    let error = ParseableExporterBuilder::from_config_file(&malformed)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.contains("malformed.json"), "{error}");
+}
@@ -0,0 +1,33 @@
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::sdk::trace;
+use opentelemetry::trace::Tracer;
+use opentelemetry::{runtime, Context, KeyValue};
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn correlation_id_is_taken_from_the_baggage() {
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_correlation_id_from_baggage("correlation_id")
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+
+    {
+        let _baggage =
+            Context::current_with_baggage(vec![KeyValue::new("correlation_id", "order-42")])
+                .attach();
+        tracer.in_span("checkout", |_| {});
+    }
+    tracer.in_span("healthcheck", |_| {});
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["correlation_id"], "order-42");
+    assert!(records[0]["attributes"].as_array().unwrap().is_empty());
+    assert!(records[1].get("correlation_id").is_none());
+}
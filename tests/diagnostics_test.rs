@@ -0,0 +1,84 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn rejecting_parseable() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(400).set_body_string("unknown field"))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn export_logging(builder: opentelemetry_parseable::ParseableExporterBuilder) -> String {
+    let logs = SharedBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut exporter = builder.build_exporter().unwrap();
+    let _ = exporter.export(vec![support::span_data("rejected")]).await;
+
+    let logs = logs.0.lock().unwrap();
+    String::from_utf8(logs.clone()).unwrap()
+}
+
+#[tokio::test]
+async fn rejections_are_logged_with_credentials_masked() {
+    let server = rejecting_parseable().await;
+    let logs = export_logging(support::builder_for(&server).with_diagnostics()).await;
+
+    assert!(logs.contains("ERROR"), "{logs}");
+    assert!(logs.contains("400"), "{logs}");
+    assert!(logs.contains("unknown field"), "{logs}");
+    assert!(
+        logs.contains("\\\"span_name\\\":\\\"rejected\\\""),
+        "{logs}"
+    );
+    assert!(logs.contains("authorization: <redacted>"), "{logs}");
+    assert!(!logs.contains("Basic"), "{logs}");
+}
+
+#[tokio::test]
+async fn rejections_are_not_logged_by_default() {
+    let server = rejecting_parseable().await;
+    let builder = support::builder_for(&server)
+        .with_username("user")
+        .with_password("pass");
+    let logs = export_logging(builder).await;
+
+    assert!(logs.is_empty(), "{logs}");
+}
+
+#[tokio::test]
+async fn default_credentials_are_warned_about() {
+    let server = rejecting_parseable().await;
+    let logs = export_logging(support::builder_for(&server)).await;
+
+    assert!(logs.contains("WARN"), "{logs}");
+    assert!(logs.contains("admin/admin"), "{logs}");
+}
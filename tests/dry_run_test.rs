@@ -0,0 +1,114 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::IngestFormat;
+use tracing::Level;
+
+mod support;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn dry_run_logs_the_batch_instead_of_sending_it() {
+    let server = support::mock_parseable().await;
+    let logs = SharedBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_max_level(Level::DEBUG)
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut exporter = support::builder_for(&server)
+        .with_dry_run(true)
+        .with_publish_schema()
+        .build_exporter()
+        .unwrap();
+    exporter
+        .export(vec![support::span_data("rehearsed")])
+        .await
+        .unwrap();
+
+    assert!(server.received_requests().await.unwrap().is_empty());
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .find(|line| line.contains("Parseable dry run"))
+        .expect("dry run is logged");
+    assert!(line.contains("test-service"), "{line}");
+    assert!(line.contains("rehearsed"), "{line}");
+}
+
+#[tokio::test]
+async fn dry_run_bodies_are_indented_with_pretty_json() {
+    for pretty in [false, true] {
+        let server = support::mock_parseable().await;
+        let logs = SharedBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(Level::DEBUG)
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut builder = support::builder_for(&server).with_dry_run(true);
+        if pretty {
+            builder = builder.with_pretty_json();
+        }
+        let mut exporter = builder.build_exporter().unwrap();
+        exporter
+            .export(vec![support::span_data("rehearsed")])
+            .await
+            .unwrap();
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            logs.contains("\n    \"span_name\": \"rehearsed\""),
+            pretty,
+            "{logs}"
+        );
+        assert_eq!(
+            logs.contains("\"span_name\":\"rehearsed\""),
+            !pretty,
+            "{logs}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn the_sink_collects_the_records_instead_of_sending_them() {
+    for format in [IngestFormat::JsonArray, IngestFormat::Ndjson] {
+        let server = support::mock_parseable().await;
+        let sink = Arc::new(Mutex::new(Vec::new()));
+        let mut exporter = support::builder_for(&server)
+            .with_ingest_format(format)
+            .with_sink(sink.clone())
+            .build_exporter()
+            .unwrap();
+
+        exporter
+            .export(vec![support::span_data("a"), support::span_data("b")])
+            .await
+            .unwrap();
+
+        assert!(server.received_requests().await.unwrap().is_empty());
+        let records = sink.lock().unwrap();
+        let names: Vec<_> = records.iter().map(|record| &record["span_name"]).collect();
+        assert_eq!(names, ["a", "b"], "{format:?}");
+    }
+}
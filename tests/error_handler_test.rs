@@ -0,0 +1,88 @@
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::ParseableExporterError;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+fn recording_handler() -> (
+    Arc<Mutex<Vec<ParseableExporterError>>>,
+    impl Fn(ParseableExporterError) + Send + Sync,
+) {
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let recorded = errors.clone();
+    (errors, move |error| recorded.lock().unwrap().push(error))
+}
+
+#[tokio::test]
+async fn handler_is_called_once_per_failed_export() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(307).insert_header("Location", "/elsewhere"))
+        .mount(&server)
+        .await;
+    let (errors, handler) = recording_handler();
+    let mut exporter = support::builder_for(&server)
+        .with_error_handler(handler)
+        .build_exporter()
+        .unwrap();
+
+    let result = exporter
+        .export(vec![support::span_data("a"), support::span_data("b")])
+        .await;
+
+    let returned = result.unwrap_err().to_string();
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, returned);
+    assert_eq!(errors[0].status.map(|s| s.as_u16()), Some(307));
+    assert_eq!(errors[0].stream, "test-service");
+    assert_eq!(errors[0].message_count, 2);
+}
+
+#[tokio::test]
+async fn handler_is_not_called_for_successful_exports() {
+    let server = support::mock_parseable().await;
+    let (errors, handler) = recording_handler();
+    let mut exporter = support::builder_for(&server)
+        .with_error_handler(handler)
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    assert!(errors.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn rejected_exports_fail_with_status_and_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(401).set_body_string("invalid credentials"))
+        .mount(&server)
+        .await;
+    let (errors, handler) = recording_handler();
+    let mut exporter = support::builder_for(&server)
+        .with_error_handler(handler)
+        .build_exporter()
+        .unwrap();
+
+    let error = exporter
+        .export(vec![support::span_data("rejected")])
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("401"), "{error}");
+    assert!(error.contains("invalid credentials"), "{error}");
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors[0].status.map(|s| s.as_u16()), Some(401));
+    assert_eq!(errors[0].body.as_deref(), Some("invalid credentials"));
+}
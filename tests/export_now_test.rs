@@ -0,0 +1,41 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+#[tokio::test]
+async fn export_now_sends_the_batch_immediately() {
+    let server = support::mock_parseable().await;
+    let exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    exporter
+        .export_now(vec![support::span_data("a"), support::span_data("b")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["span_name"], "a");
+}
+
+#[tokio::test]
+async fn export_now_returns_the_rejection() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(400).set_body_string("stream not found"))
+        .mount(&server)
+        .await;
+    let exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let error = exporter
+        .export_now(vec![support::span_data("span")])
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status.map(|s| s.as_u16()), Some(400));
+    assert_eq!(error.body.as_deref(), Some("stream not found"));
+    assert_eq!(error.stream, "test-service");
+    assert_eq!(error.message_count, 1);
+    assert!(error.message.contains("400"), "{}", error.message);
+}
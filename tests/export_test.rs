@@ -0,0 +1,1162 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::{Duration, SystemTime};
+
+use flate2::read::GzDecoder;
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::sdk::trace::{EvictedHashMap, EvictedQueue};
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::{
+    Event, Link, SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_parseable::{
+    AttributeType, CaseMode, Compression, DurationUnit, IngestFormat, MixedServices,
+    NormalizeOptions, NullHandling, RedactionMode, TimestampPrecision,
+};
+
+mod support;
+
+#[tokio::test]
+async fn small_payload_is_sent_uncompressed() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_compression(Compression::Gzip)
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("small")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(support::header(&requests[0], "content-encoding"), None);
+    let records: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(records[0]["span_name"], "small");
+}
+
+#[tokio::test]
+async fn large_payload_is_gzipped() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_compression(Compression::Gzip)
+        .with_compression_threshold(1024)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("large");
+    span.attributes
+        .insert(KeyValue::new("payload", "x".repeat(4096)));
+    exporter.export(vec![span]).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        support::header(&requests[0], "content-encoding").as_deref(),
+        Some("gzip")
+    );
+    assert_eq!(
+        support::header(&requests[0], "content-type").as_deref(),
+        Some("application/json")
+    );
+    let mut body = String::new();
+    GzDecoder::new(requests[0].body.as_slice())
+        .read_to_string(&mut body)
+        .unwrap();
+    let records: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(records[0]["span_name"], "large");
+}
+
+#[tokio::test]
+async fn empty_batch_sends_no_request() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    exporter.export(Vec::new()).await.unwrap();
+
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn only_configured_span_kinds_are_exported() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_export_span_kinds([SpanKind::Server])
+        .build_exporter()
+        .unwrap();
+
+    let mut server_span = support::span_data("server");
+    server_span.span_kind = SpanKind::Server;
+    exporter
+        .export(vec![support::span_data("internal"), server_span])
+        .await
+        .unwrap();
+    exporter
+        .export(vec![support::span_data("internal only")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "server");
+}
+
+#[tokio::test]
+async fn span_kind_is_exported_in_lowercase() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let spans = [SpanKind::Server, SpanKind::Client, SpanKind::Internal].map(|kind| {
+        let mut span = support::span_data("kind");
+        span.span_kind = kind;
+        span
+    });
+    exporter.export(spans.into()).await.unwrap();
+
+    let kinds: Vec<_> = support::received_records(&server)
+        .await
+        .iter()
+        .map(|record| record["span_kind"].clone())
+        .collect();
+    assert_eq!(kinds, ["server", "client", "internal"]);
+}
+
+#[tokio::test]
+async fn links_are_exported_with_their_attributes() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut linked = support::span_data("linked");
+    linked.links.extend([Link::new(
+        SpanContext::new(
+            TraceId::from_bytes(7u128.to_be_bytes()),
+            SpanId::from_bytes(8u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        ),
+        vec![KeyValue::new("link.reason", "fan-in")],
+    )]);
+    exporter
+        .export(vec![linked, support::span_data("unlinked")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["links"],
+        serde_json::json!([{
+            "trace_id": "00000000000000000000000000000007",
+            "span_id": "0000000000000008",
+            "attributes": ["link.reason=fan-in"],
+        }])
+    );
+    assert_eq!(records[1]["links"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn attributes_and_events_dropped_by_span_limits_are_counted() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut span = support::span_data("limited");
+    span.attributes = EvictedHashMap::new(1, 3);
+    for key in ["a", "b", "c"] {
+        span.attributes.insert(KeyValue::new(key, "value"));
+    }
+    span.events = EvictedQueue::new(1);
+    span.events.extend(
+        ["first", "second"].map(|name| Event::new(name, std::time::SystemTime::now(), vec![], 0)),
+    );
+    exporter
+        .export(vec![span, support::span_data("unlimited")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["dropped_attributes_count"], 2);
+    assert_eq!(records[0]["dropped_events_count"], 1);
+    assert_eq!(records[1]["dropped_attributes_count"], 0);
+    assert_eq!(records[1]["dropped_events_count"], 0);
+}
+
+#[tokio::test]
+async fn spans_deeper_than_max_trace_depth_are_dropped() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_max_trace_depth(1)
+        .build_exporter()
+        .unwrap();
+
+    let id = |span_id: u64| SpanId::from_bytes(span_id.to_be_bytes());
+    exporter
+        .export(vec![
+            support::span_data_with_ids("root", 1, 1, SpanId::INVALID),
+            support::span_data_with_ids("child", 1, 2, id(1)),
+            support::span_data_with_ids("grandchild", 1, 3, id(2)),
+            support::span_data_with_ids("great-grandchild", 1, 4, id(3)),
+            // The parent is in another batch, so all that's known is a depth of one.
+            support::span_data_with_ids("orphan", 2, 5, id(9)),
+            support::span_data_with_ids("orphan child", 2, 6, id(5)),
+        ])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    let names: Vec<_> = records
+        .iter()
+        .map(|record| record["span_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["root", "child", "orphan"]);
+}
+
+#[tokio::test]
+async fn spans_without_a_parent_in_the_batch_are_local_roots() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_local_root()
+        .build_exporter()
+        .unwrap();
+
+    let id = |span_id: u64| SpanId::from_bytes(span_id.to_be_bytes());
+    exporter
+        .export(vec![
+            support::span_data_with_ids("root", 1, 1, SpanId::INVALID),
+            support::span_data_with_ids("child", 1, 2, id(1)),
+            // Continues a trace started by another service.
+            support::span_data_with_ids("remote child", 2, 3, id(9)),
+            support::span_data_with_ids("local child", 2, 4, id(3)),
+        ])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    let local_roots: Vec<_> = records
+        .iter()
+        .map(|record| record["local_root"].as_bool().unwrap())
+        .collect();
+    assert_eq!(local_roots, [true, false, true, false]);
+}
+
+#[tokio::test]
+async fn mixed_service_batches_are_split_per_service_stream() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_mixed_services(MixedServices::Split)
+        .build_exporter()
+        .unwrap();
+
+    let mut worker_span = support::span_data("job");
+    worker_span.resource = Cow::Owned(Resource::new([KeyValue::new("service.name", "worker")]));
+    exporter
+        .export(vec![
+            support::span_data("request"),
+            worker_span,
+            support::span_data("response"),
+        ])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let streams: Vec<_> = requests
+        .iter()
+        .map(|request| support::header(request, "X-P-Stream").unwrap())
+        .collect();
+    assert_eq!(streams, ["test-service", "worker"]);
+    let names = |request: &wiremock::Request| -> Vec<String> {
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+        records
+            .iter()
+            .map(|record| record["span_name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(names(&requests[0]), ["request", "response"]);
+    assert_eq!(names(&requests[1]), ["job"]);
+}
+
+#[tokio::test]
+async fn mixed_service_batches_go_to_one_stream_by_default() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut worker_span = support::span_data("job");
+    worker_span.resource = Cow::Owned(Resource::new([KeyValue::new("service.name", "worker")]));
+    exporter
+        .export(vec![support::span_data("request"), worker_span])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        support::header(&requests[0], "X-P-Stream").as_deref(),
+        Some("test-service")
+    );
+    assert_eq!(support::received_records(&server).await.len(), 2);
+}
+
+#[tokio::test]
+async fn envelope_hoists_shared_metadata_out_of_the_records() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_envelope_format()
+        .with_exporter_id()
+        .with_stream_field()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![
+            support::span_data("first"),
+            support::span_data("second"),
+        ])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let meta = body["meta"].as_object().unwrap();
+    let mut keys: Vec<_> = meta.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, ["exporter_id", "resource_attributes", "stream"]);
+    assert_eq!(
+        meta["resource_attributes"],
+        serde_json::json!(["service.name=test-service"])
+    );
+    assert_eq!(meta["stream"], "test-service");
+    let records = body["records"].as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        assert!(record.get("resource_attributes").is_none(), "{record}");
+        assert!(record.get("exporter_id").is_none(), "{record}");
+        assert!(record.get("stream").is_none(), "{record}");
+        assert!(record.get("span_name").is_some(), "{record}");
+    }
+}
+
+#[tokio::test]
+async fn envelope_keeps_resource_attributes_that_differ_on_the_records() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_envelope_format()
+        .build_exporter()
+        .unwrap();
+
+    let mut worker_span = support::span_data("job");
+    worker_span.resource = Cow::Owned(Resource::new([KeyValue::new("service.name", "worker")]));
+    exporter
+        .export(vec![support::span_data("request"), worker_span])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["meta"], serde_json::json!({}));
+    assert_eq!(
+        body["records"][1]["resource_attributes"],
+        serde_json::json!(["service.name=worker"])
+    );
+}
+
+#[tokio::test]
+async fn interleaved_resources_are_each_exported_with_their_spans() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let worker_span = |name| {
+        let mut span = support::span_data(name);
+        span.resource = Cow::Owned(Resource::new([KeyValue::new("service.name", "worker")]));
+        span
+    };
+    exporter
+        .export(vec![
+            worker_span("first_job"),
+            worker_span("second_job"),
+            support::span_data("request"),
+            worker_span("third_job"),
+        ])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    let services: Vec<_> = records
+        .iter()
+        .map(|record| record["resource_attributes"][0].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        services,
+        [
+            "service.name=worker",
+            "service.name=worker",
+            "service.name=test-service",
+            "service.name=worker",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn messages_from_one_exporter_share_exporter_id() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_exporter_id()
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("with-events");
+    span.events.append_vec(&mut vec![
+        Event::with_name("first"),
+        Event::with_name("second"),
+    ]);
+    exporter.export(vec![span]).await.unwrap();
+    exporter
+        .export(vec![support::span_data("plain")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 3);
+    let exporter_id = records[0]["exporter_id"].as_str().unwrap();
+    assert!(uuid::Uuid::parse_str(exporter_id).is_ok());
+    assert!(records.iter().all(|r| r["exporter_id"] == exporter_id));
+}
+
+#[tokio::test]
+async fn exporter_id_is_omitted_by_default() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    exporter
+        .export(vec![support::span_data("plain")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert!(records[0].get("exporter_id").is_none());
+}
+
+#[tokio::test]
+async fn records_are_sorted_by_end_time() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_sort_by_end_time()
+        .build_exporter()
+        .unwrap();
+
+    let spans = [("third", 300), ("first", 100), ("second", 200)].map(|(name, millis)| {
+        let mut span = support::span_data(name);
+        span.end_time = span.start_time + Duration::from_millis(millis);
+        span
+    });
+    exporter.export(spans.into()).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let names: Vec<&str> = records
+        .iter()
+        .map(|r| r["span_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn sampled_flag_is_exported_as_a_boolean() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut unsampled = support::span_data("unsampled");
+    let context = &unsampled.span_context;
+    unsampled.span_context = SpanContext::new(
+        context.trace_id(),
+        context.span_id(),
+        TraceFlags::default(),
+        false,
+        TraceState::default(),
+    );
+    exporter
+        .export(vec![support::span_data("sampled"), unsampled])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["sampled"], true);
+    assert_eq!(records[1]["sampled"], false);
+}
+
+#[tokio::test]
+async fn trace_state_is_exported_as_its_header() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut with_state = support::span_data("with_state");
+    let context = &with_state.span_context;
+    with_state.span_context = SpanContext::new(
+        context.trace_id(),
+        context.span_id(),
+        context.trace_flags(),
+        false,
+        TraceState::from_key_value([("rojo", "00f067aa0ba902b7")]).unwrap(),
+    );
+    exporter
+        .export(vec![with_state, support::span_data("without_state")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["trace_state"], "rojo=00f067aa0ba902b7");
+    assert_eq!(records[1]["trace_state"], "");
+}
+
+#[tokio::test]
+async fn status_code_is_exported_as_name_or_number() {
+    for (numeric, expected) in [
+        (false, serde_json::json!(["UNSET", "OK", "ERROR"])),
+        (true, serde_json::json!([0, 1, 2])),
+    ] {
+        let server = support::mock_parseable().await;
+        let mut builder = support::builder_for(&server);
+        if numeric {
+            builder = builder.with_numeric_status();
+        }
+        let mut exporter = builder.build_exporter().unwrap();
+
+        let spans = [Status::Unset, Status::Ok, Status::error("failed")].map(|status| {
+            let mut span = support::span_data("status");
+            span.status = status;
+            span
+        });
+        exporter.export(spans.into()).await.unwrap();
+
+        let statuses: Vec<serde_json::Value> = support::received_records(&server)
+            .await
+            .iter()
+            .map(|r| r["status_code"].clone())
+            .collect();
+        assert_eq!(serde_json::Value::from(statuses), expected);
+    }
+}
+
+#[tokio::test]
+async fn error_description_is_exported_as_status_message() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let spans = [Status::Ok, Status::error("connection reset")].map(|status| {
+        let mut span = support::span_data("status");
+        span.status = status;
+        span
+    });
+    exporter.export(spans.into()).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert!(records[0].get("status_message").is_none());
+    assert_eq!(records[1]["status_code"], "ERROR");
+    assert_eq!(records[1]["status_message"], "connection reset");
+}
+
+#[tokio::test]
+async fn duration_is_an_integer_in_the_configured_unit() {
+    for (unit, field, expected) in [
+        (DurationUnit::Millis, "duration_ms", 1),
+        (DurationUnit::Micros, "duration_us", 1_500),
+        (DurationUnit::Nanos, "duration_ns", 1_500_000),
+    ] {
+        let server = support::mock_parseable().await;
+        let mut exporter = support::builder_for(&server)
+            .with_duration_unit(unit)
+            .build_exporter()
+            .unwrap();
+
+        let mut span = support::span_data("timed");
+        span.end_time = span.start_time + Duration::from_micros(1_500);
+        exporter.export(vec![span]).await.unwrap();
+
+        let records = support::received_records(&server).await;
+        assert!(records[0][field].is_u64(), "{field} is not an integer");
+        assert_eq!(records[0][field], expected);
+    }
+}
+
+#[tokio::test]
+async fn timestamps_have_the_configured_precision() {
+    for (precision, expected) in [
+        (TimestampPrecision::Millis, "2023-11-14T22:13:20.000Z"),
+        (TimestampPrecision::Micros, "2023-11-14T22:13:20.000001Z"),
+        (TimestampPrecision::Nanos, "2023-11-14T22:13:20.000001500Z"),
+    ] {
+        let server = support::mock_parseable().await;
+        let mut exporter = support::builder_for(&server)
+            .with_timestamp_precision(precision)
+            .build_exporter()
+            .unwrap();
+
+        let mut span = support::span_data("precise");
+        span.start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        span.end_time = span.start_time + Duration::from_nanos(1_500);
+        exporter.export(vec![span]).await.unwrap();
+
+        let records = support::received_records(&server).await;
+        assert_eq!(records[0]["end_time"], expected);
+    }
+}
+
+#[tokio::test]
+async fn duration_is_clamped_when_span_ends_before_it_starts() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut span = support::span_data("backwards");
+    span.end_time = span.start_time - Duration::from_millis(5);
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["duration_ms"], 0);
+}
+
+#[tokio::test]
+async fn string_interning_does_not_change_records() {
+    let mut outputs = Vec::new();
+    for interning in [false, true] {
+        let server = support::mock_parseable().await;
+        let mut builder = support::builder_for(&server);
+        if interning {
+            builder = builder.with_string_interning();
+        }
+        let mut exporter = builder.build_exporter().unwrap();
+
+        let spans = (0..3)
+            .map(|_| {
+                let mut span = support::span_data("span");
+                span.attributes.insert(KeyValue::new("http.method", "GET"));
+                span
+            })
+            .collect();
+        exporter.export(spans).await.unwrap();
+        outputs.push(support::received_records(&server).await);
+    }
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+#[tokio::test]
+async fn attribute_keys_are_remapped() {
+    let server = support::mock_parseable().await;
+    let key_map = HashMap::from([("http.method".to_string(), "method".to_string())]);
+    let mut exporter = support::builder_for(&server)
+        .with_attribute_key_map(key_map)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes.insert(KeyValue::new("http.method", "GET"));
+    span.attributes.insert(KeyValue::new("http.route", "/api"));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let mut attributes = records[0]["attributes"].as_array().unwrap().clone();
+    attributes.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    assert_eq!(attributes, ["http.route=/api", "method=GET"]);
+    assert_eq!(
+        records[0]["resource_attributes"],
+        serde_json::json!(["service.name=test-service"])
+    );
+}
+
+#[tokio::test]
+async fn missing_optional_fields_follow_null_handling() {
+    for (null_handling, expected) in [
+        (NullHandling::OmitNull, None),
+        (NullHandling::EmitNull, Some(serde_json::Value::Null)),
+    ] {
+        let server = support::mock_parseable().await;
+        let mut exporter = support::builder_for(&server)
+            .with_null_handling(null_handling)
+            .build_exporter()
+            .unwrap();
+
+        let mut with_event = support::span_data("with event");
+        with_event
+            .events
+            .append_vec(&mut vec![Event::with_name("happened")]);
+        exporter
+            .export(vec![support::span_data("plain"), with_event])
+            .await
+            .unwrap();
+
+        let records = support::received_records(&server).await;
+        assert_eq!(records[0].get("event_message").cloned(), expected);
+        assert_eq!(records[0].get("event_timestamp").cloned(), expected);
+        assert_eq!(records[1]["event_message"], "happened");
+        assert!(records[1]["event_timestamp"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn event_attributes_are_kept_apart_from_span_attributes() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut span = support::span_data("with event");
+    span.attributes.insert(KeyValue::new("http.method", "GET"));
+    span.events.append_vec(&mut vec![Event::new(
+        "retry",
+        std::time::SystemTime::now(),
+        vec![KeyValue::new("attempt", 2)],
+        0,
+    )]);
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["http.method=GET"])
+    );
+    assert_eq!(
+        records[0]["event_attributes"],
+        serde_json::json!(["attempt=2"])
+    );
+}
+
+#[tokio::test]
+async fn only_configured_resource_attributes_are_written() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_resource_attribute_keys(vec!["service.name".into()])
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("trimmed");
+    span.resource = Cow::Owned(Resource::new([
+        KeyValue::new("service.name", "test-service"),
+        KeyValue::new("host.name", "build-agent-7"),
+    ]));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["resource_attributes"],
+        serde_json::json!(["service.name=test-service"])
+    );
+}
+
+#[tokio::test]
+async fn ndjson_sends_one_record_per_line() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_ingest_format(IngestFormat::Ndjson)
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![
+            support::span_data("first"),
+            support::span_data("second"),
+        ])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        support::header(&requests[0], "Content-Type").as_deref(),
+        Some("application/x-ndjson")
+    );
+    let body = String::from_utf8(requests[0].body.clone()).unwrap();
+    let names: Vec<_> = body
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["span_name"].clone())
+        .collect();
+    assert_eq!(names, ["first", "second"]);
+    assert!(body.ends_with('\n'));
+}
+
+#[tokio::test]
+async fn redacted_attributes_are_dropped_masked_or_hashed() {
+    for (mode, expected) in [
+        (RedactionMode::Drop, vec!["http.method=GET"]),
+        (
+            RedactionMode::Mask,
+            vec![
+                "User.Email=***",
+                "http.method=GET",
+                "http.request.header.authorization=***",
+            ],
+        ),
+        (
+            RedactionMode::Hash,
+            vec![
+                "User.Email=f4e19df2e6c609fbd59a42b9063d0fadf44260218531ea21ad8c575f205c0453",
+                "http.method=GET",
+                "http.request.header.authorization=c355dce96c1612880d11940ffdd9014d386c253e0c3652a6cd06a7226f7bd2b6",
+            ],
+        ),
+    ] {
+        let server = support::mock_parseable().await;
+        let mut exporter = support::builder_for(&server)
+            .with_redacted_keys(vec![
+                "user.email".to_string(),
+                "http.request.header.*".to_string(),
+            ])
+            .with_redaction_mode(mode)
+            .build_exporter()
+            .unwrap();
+
+        let mut span = support::span_data("redacted");
+        span.attributes.insert(KeyValue::new("http.method", "GET"));
+        span.attributes
+            .insert(KeyValue::new("User.Email", "jo@example.com"));
+        span.attributes.insert(KeyValue::new(
+            "http.request.header.authorization",
+            "Bearer abc",
+        ));
+        exporter.export(vec![span]).await.unwrap();
+
+        let records = support::received_records(&server).await;
+        let mut attributes: Vec<String> =
+            serde_json::from_value(records[0]["attributes"].clone()).unwrap();
+        attributes.sort();
+        assert_eq!(attributes, expected, "{mode:?}");
+    }
+}
+
+#[tokio::test]
+async fn only_allowlisted_attributes_are_exported() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_attribute_allowlist(["http.method".to_string(), "attempt".to_string()].into())
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("with event");
+    span.attributes.insert(KeyValue::new("http.method", "GET"));
+    span.attributes
+        .insert(KeyValue::new("user.email", "jo@example.com"));
+    span.events.append_vec(&mut vec![Event::new(
+        "retry",
+        std::time::SystemTime::now(),
+        vec![KeyValue::new("attempt", 2), KeyValue::new("token", "abc")],
+        0,
+    )]);
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["http.method=GET"])
+    );
+    assert_eq!(
+        records[0]["event_attributes"],
+        serde_json::json!(["attempt=2"])
+    );
+}
+
+#[tokio::test]
+async fn long_arrays_are_truncated_and_flagged() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_max_array_elements(2)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("tagged");
+    span.attributes.insert(KeyValue::new(
+        "tags",
+        Value::Array(vec![1_i64, 2, 3, 4, 5].into()),
+    ));
+    span.attributes
+        .insert(KeyValue::new("ids", Value::Array(vec![7_i64].into())));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let attributes = records[0]["attributes"].as_array().unwrap();
+    assert!(attributes.contains(&serde_json::json!("tags=[1,2]")));
+    assert!(attributes.contains(&serde_json::json!("tags.truncated=3")));
+    assert!(attributes.contains(&serde_json::json!("ids=[7]")));
+    assert!(!attributes.contains(&serde_json::json!("ids.truncated=0")));
+    assert_eq!(attributes.len(), 3);
+}
+
+#[tokio::test]
+async fn attribute_keys_are_lowercased_and_collisions_resolved() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_attribute_key_case(CaseMode::Lower)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("mixed case");
+    span.attributes.insert(KeyValue::new("HTTP.Method", "POST"));
+    span.attributes.insert(KeyValue::new("http.method", "GET"));
+    span.attributes.insert(KeyValue::new("Net.Peer.Port", 443));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let mut attributes: Vec<&str> = records[0]["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    attributes.sort();
+    assert_eq!(attributes, ["http.method=GET", "net.peer.port=443"]);
+}
+
+#[tokio::test]
+async fn values_are_trimmed_and_lowercased_for_configured_keys() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_value_normalization(
+            NormalizeOptions::new()
+                .trim_whitespace()
+                .lowercase(["http.method"]),
+        )
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes
+        .insert(KeyValue::new("http.method", " GET "));
+    span.attributes.insert(KeyValue::new("user.name", "Alice "));
+    span.attributes.insert(KeyValue::new("retry", true));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let mut attributes = records[0]["attributes"].as_array().unwrap().clone();
+    attributes.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    assert_eq!(
+        attributes,
+        ["http.method=get", "retry=true", "user.name=Alice"]
+    );
+}
+
+#[tokio::test]
+async fn values_are_not_normalized_by_default() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes
+        .insert(KeyValue::new("http.method", " GET "));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["http.method= GET "])
+    );
+}
+
+#[tokio::test]
+async fn stream_field_matches_the_stream_header() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_stream_field()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("a"), support::span_data("b")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let stream = support::header(&requests[0], "x-p-stream").unwrap();
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|r| r["stream"] == stream.as_str()));
+
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+    exporter
+        .export(vec![support::span_data("plain")])
+        .await
+        .unwrap();
+    assert!(support::received_records(&server).await[0]
+        .get("stream")
+        .is_none());
+}
+
+#[tokio::test]
+async fn typed_attributes_keep_value_types() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_typed_attributes()
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes.insert(KeyValue::new("http.method", "GET"));
+    span.attributes
+        .insert(KeyValue::new("http.status_code", 200_i64));
+    span.attributes.insert(KeyValue::new("retry", false));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!({"http.method": "GET", "http.status_code": 200, "retry": false})
+    );
+    assert_eq!(
+        records[0]["resource_attributes"],
+        serde_json::json!({"service.name": "test-service"})
+    );
+}
+
+#[tokio::test]
+async fn namespaced_attributes_separate_resource_span_and_event() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_namespaced_attributes()
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes
+        .insert(KeyValue::new("http.status_code", 200_i64));
+    span.events.append_vec(&mut vec![Event::new(
+        "retry",
+        span.start_time,
+        vec![
+            KeyValue::new("attempt", 2_i64),
+            KeyValue::new("final", true),
+        ],
+        0,
+    )]);
+    exporter
+        .export(vec![span, support::span_data("without events")])
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!({
+            "resource": {"service.name": "test-service"},
+            "span": {"http.status_code": 200},
+            "event": {"attempt": 2, "final": true},
+        })
+    );
+    assert_eq!(records[1]["attributes"]["event"], serde_json::json!({}));
+    for record in &records {
+        assert!(record.get("resource_attributes").is_none(), "{record}");
+        assert!(record.get("event_attributes").is_none(), "{record}");
+    }
+}
+
+#[tokio::test]
+async fn annotated_attributes_carry_a_unit_companion() {
+    let server = support::mock_parseable().await;
+    let units = HashMap::from([("db.duration".to_string(), "ms".to_string())]);
+    let mut exporter = support::builder_for(&server)
+        .with_attribute_units(units.clone())
+        .build_exporter()
+        .unwrap();
+    let mut typed_exporter = support::builder_for(&server)
+        .with_typed_attributes()
+        .with_attribute_units(units)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("query");
+    span.attributes.insert(KeyValue::new("db.duration", 12_i64));
+    span.attributes.insert(KeyValue::new("db.rows", 3_i64));
+    exporter.export(vec![span.clone()]).await.unwrap();
+    typed_exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let mut flat: Vec<_> = records[0]["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|attribute| attribute.as_str().unwrap())
+        .collect();
+    flat.sort_unstable();
+    assert_eq!(flat, ["db.duration=12", "db.duration_unit=ms", "db.rows=3"]);
+    assert_eq!(
+        records[1]["attributes"],
+        serde_json::json!({"db.duration": 12, "db.duration_unit": "ms", "db.rows": 3})
+    );
+}
+
+#[tokio::test]
+async fn json_attribute_values_are_embedded_as_objects() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_typed_attributes()
+        .with_json_attribute_parsing(["data", "broken"])
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes.insert(KeyValue::new(
+        "data",
+        r#"{"user": {"id": 7}, "tags": ["a", "b"]}"#,
+    ));
+    span.attributes.insert(KeyValue::new("broken", "{not json"));
+    span.attributes
+        .insert(KeyValue::new("other", r#"{"kept": "as string"}"#));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"]["data"],
+        serde_json::json!({"user": {"id": 7}, "tags": ["a", "b"]})
+    );
+    assert_eq!(records[0]["attributes"]["broken"], "{not json");
+    assert_eq!(
+        records[0]["attributes"]["other"],
+        r#"{"kept": "as string"}"#
+    );
+}
+
+#[tokio::test]
+async fn json_attribute_values_are_flattened_into_strings() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_json_attribute_parsing(["data"])
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes.insert(KeyValue::new(
+        "data",
+        r#"{"user": {"id": 7, "name": "ann"}, "tags": ["a"]}"#,
+    ));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    let mut attributes: Vec<_> = records[0]["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|attribute| attribute.as_str().unwrap())
+        .collect();
+    attributes.sort_unstable();
+    assert_eq!(
+        attributes,
+        [r#"data.tags=["a"]"#, "data.user.id=7", "data.user.name=ann"]
+    );
+}
+
+#[tokio::test]
+async fn attribute_values_are_coerced_to_the_configured_type() {
+    let server = support::mock_parseable().await;
+    let rules = HashMap::from([
+        ("http.status_code".to_string(), AttributeType::Int),
+        ("http.route".to_string(), AttributeType::Int),
+    ]);
+    let mut exporter = support::builder_for(&server)
+        .with_typed_attributes()
+        .with_type_coercion(rules)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.attributes
+        .insert(KeyValue::new("http.status_code", "200"));
+    span.attributes.insert(KeyValue::new("http.route", "/api"));
+    exporter.export(vec![span]).await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["attributes"]["http.status_code"], 200);
+    assert_eq!(records[0]["attributes"]["http.route"], "/api");
+}
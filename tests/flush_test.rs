@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use opentelemetry::runtime;
+use opentelemetry::sdk::trace;
+use opentelemetry::trace::{Span as _, Tracer};
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn flush_exports_the_buffered_spans_of_the_installed_provider() {
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_scheduled_delay(Duration::from_secs(3600))
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+
+    tracer.start("invocation").end();
+    assert!(support::received_records(&server).await.is_empty());
+    opentelemetry_parseable::flush().await.unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "invocation");
+}
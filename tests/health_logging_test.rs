@@ -0,0 +1,107 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::ExporterStats;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn health_logs(builder: opentelemetry_parseable::ParseableExporterBuilder) -> Vec<String> {
+    let logs = SharedBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut exporter = builder.build_exporter().unwrap();
+    exporter
+        .export(vec![support::span_data("checked")])
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(350)).await;
+
+    let logs = logs.0.lock().unwrap();
+    String::from_utf8(logs.clone())
+        .unwrap()
+        .lines()
+        .filter(|line| line.contains("Parseable exporter health"))
+        .map(String::from)
+        .collect()
+}
+
+#[tokio::test]
+async fn stats_are_logged_every_interval() {
+    let server = support::mock_parseable().await;
+    let logs =
+        health_logs(support::builder_for(&server).with_health_logging(Duration::from_millis(100)))
+            .await;
+
+    assert!((2..=4).contains(&logs.len()), "{logs:#?}");
+    for line in &logs {
+        assert!(line.contains("INFO"), "{line}");
+        assert!(line.contains("stream=\"test-service\""), "{line}");
+        assert!(line.contains("records_exported=1"), "{line}");
+        assert!(line.contains("records_failed=0"), "{line}");
+        assert!(!line.contains("last_success=never"), "{line}");
+    }
+}
+
+#[tokio::test]
+async fn stats_are_not_logged_by_default() {
+    let server = support::mock_parseable().await;
+    let logs = health_logs(support::builder_for(&server)).await;
+
+    assert!(logs.is_empty(), "{logs:#?}");
+}
+
+#[tokio::test]
+async fn stats_count_exported_and_failed_batches_across_clones() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+    let handle = exporter.clone();
+    assert_eq!(handle.stats(), ExporterStats::default());
+
+    exporter
+        .export(vec![support::span_data("a"), support::span_data("b")])
+        .await
+        .unwrap();
+    exporter
+        .export(vec![support::span_data("c")])
+        .await
+        .unwrap_err();
+
+    let stats = handle.stats();
+    assert_eq!(stats.batches_exported, 1);
+    assert_eq!(stats.records_exported, 2);
+    assert_eq!(stats.batches_failed, 1);
+    assert_eq!(stats.records_failed, 1);
+    assert!(stats.last_success.is_some());
+}
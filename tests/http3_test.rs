@@ -0,0 +1,22 @@
+#![cfg(feature = "http3")]
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+
+mod support;
+
+// The mock server only listens on TCP, so this exercises the fallback from HTTP/3.
+#[tokio::test]
+async fn http3_export_reaches_parseable() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_http3()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    assert_eq!(support::received_records(&server).await.len(), 1);
+}
@@ -49,8 +49,21 @@ mod example {
 #[instrument]
 async fn run() {
     //println!("Name: {name}");
-    common::telemetry_startup().await;
+    common::telemetry_startup(None, common::StdoutFormat::Plain).await;
     example::fooz().await;
     example::outside_test().await;
     common::telemetry_shutdown().await;
 }
+
+#[test]
+fn service_name_is_the_exe_name_up_to_the_first_dash() {
+    for (exe, expected) in [
+        ("integration_test-3f2a9c1b8d7e6f50", "integration_test"),
+        ("my-service_v2", "my"),
+        ("api-1.2.3", "api"),
+        ("my_service", "my_service"),
+        ("collector", "collector"),
+    ] {
+        assert_eq!(common::service_name_from_exe(exe), expected, "{exe}");
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use opentelemetry_parseable::JitterStrategy;
+
+const BASE: Duration = Duration::from_millis(100);
+const CAP: Duration = Duration::from_secs(2);
+
+/// Smallest and largest of many delays for retry `attempt`.
+fn delay_range(strategy: JitterStrategy, attempt: u32, previous: Duration) -> (Duration, Duration) {
+    let delays: Vec<_> = (0..1000)
+        .map(|_| strategy.delay(BASE, CAP, attempt, previous))
+        .collect();
+    (*delays.iter().min().unwrap(), *delays.iter().max().unwrap())
+}
+
+#[test]
+fn no_jitter_backs_off_exponentially_up_to_the_cap() {
+    let backoffs: Vec<_> = (0..6)
+        .map(|attempt| JitterStrategy::None.delay(BASE, CAP, attempt, BASE))
+        .collect();
+    let millis = |ms: u64| Duration::from_millis(ms);
+    assert_eq!(
+        backoffs,
+        [
+            millis(100),
+            millis(200),
+            millis(400),
+            millis(800),
+            millis(1600),
+            CAP
+        ]
+    );
+    assert_eq!(JitterStrategy::None.delay(BASE, CAP, u32::MAX, BASE), CAP);
+}
+
+#[test]
+fn full_jitter_ranges_from_zero_to_the_backoff() {
+    let (min, max) = delay_range(JitterStrategy::Full, 2, BASE);
+    assert!(min < Duration::from_millis(100), "{min:?}");
+    assert!(max > Duration::from_millis(300), "{max:?}");
+    assert!(max <= Duration::from_millis(400), "{max:?}");
+}
+
+#[test]
+fn equal_jitter_keeps_at_least_half_the_backoff() {
+    let (min, max) = delay_range(JitterStrategy::Equal, 2, BASE);
+    assert!(min >= Duration::from_millis(200), "{min:?}");
+    assert!(min < Duration::from_millis(250), "{min:?}");
+    assert!(max > Duration::from_millis(350), "{max:?}");
+    assert!(max <= Duration::from_millis(400), "{max:?}");
+}
+
+#[test]
+fn decorrelated_jitter_grows_from_the_previous_delay() {
+    let (min, max) = delay_range(JitterStrategy::Decorrelated, 0, Duration::from_millis(300));
+    assert!(min >= BASE, "{min:?}");
+    assert!(min < Duration::from_millis(200), "{min:?}");
+    assert!(max > Duration::from_millis(800), "{max:?}");
+    assert!(max <= Duration::from_millis(900), "{max:?}");
+
+    let (_, max) = delay_range(JitterStrategy::Decorrelated, 0, Duration::from_secs(10));
+    assert_eq!(max, CAP);
+}
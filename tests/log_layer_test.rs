@@ -0,0 +1,133 @@
+use std::time::{Duration, SystemTime};
+
+use opentelemetry_parseable::{FixedClock, TimestampPrecision};
+use tracing_subscriber::prelude::*;
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_outside_spans_are_exported_to_the_logs_stream() {
+    let server = support::mock_parseable().await;
+    let layer = support::builder_for(&server).build_log_layer().unwrap();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+    {
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!(user = "alice", attempts = 3, "signed in");
+        tracing::info_span!("request").in_scope(|| tracing::info!("inside a span"));
+    }
+    layer.flush().await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        support::header(&requests[0], "X-P-Stream").as_deref(),
+        Some("test-service-logs")
+    );
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["message"], "signed in");
+    assert_eq!(records[0]["level"], "INFO");
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["user=alice", "attempts=3"])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn event_fields_are_redacted() {
+    let server = support::mock_parseable().await;
+    let layer = support::builder_for(&server)
+        .with_redacted_keys(vec!["user.email".to_string()])
+        .build_log_layer()
+        .unwrap();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+    {
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!(user.email = "alice@example.com", attempts = 3, "signed in");
+    }
+    layer.flush().await;
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["user.email=***", "attempts=3"])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_are_stamped_by_the_configured_clock() {
+    let server = support::mock_parseable().await;
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let layer = support::builder_for(&server)
+        .with_clock(FixedClock(now))
+        .build_log_layer()
+        .unwrap();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+    {
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!("signed in");
+    }
+    layer.flush().await;
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["timestamp"], "2023-11-14T22:13:20.000Z");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_are_stamped_with_the_configured_precision() {
+    let server = support::mock_parseable().await;
+    let now = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 1_500);
+    let layer = support::builder_for(&server)
+        .with_clock(FixedClock(now))
+        .with_timestamp_precision(TimestampPrecision::Nanos)
+        .build_log_layer()
+        .unwrap();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+    {
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!("signed in");
+    }
+    layer.flush().await;
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["timestamp"], "2023-11-14T22:13:20.000001500Z");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn event_fields_keep_their_json_types() {
+    let server = support::mock_parseable().await;
+    let layer = support::builder_for(&server)
+        .with_typed_attributes()
+        .build_log_layer()
+        .unwrap();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+
+    {
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!(
+            attempts = 3_i64,
+            bytes = 512_u64,
+            ratio = 0.5,
+            retried = true,
+            user = "alice",
+            "signed in"
+        );
+    }
+    layer.flush().await;
+
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!({
+            "attempts": 3,
+            "bytes": 512,
+            "ratio": 0.5,
+            "retried": true,
+            "user": "alice",
+        })
+    );
+}
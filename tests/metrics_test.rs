@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry::sdk::export::metrics::aggregation;
+use opentelemetry::sdk::metrics::{controllers, processors, selectors};
+use opentelemetry::{runtime, Context, KeyValue};
+use opentelemetry_parseable::{ParseableExporterBuilder, TimestampPrecision};
+use wiremock::{MockServer, Request};
+
+mod support;
+
+/// Count 3 requests with the given attributes and return the metrics request sent once
+/// the controller stops.
+async fn export_counter(
+    server: &MockServer,
+    builder: ParseableExporterBuilder,
+    attributes: &[KeyValue],
+) -> Request {
+    let exporter = builder.build_metrics_exporter().unwrap();
+    let controller = controllers::basic(processors::factory(
+        selectors::simple::inexpensive(),
+        aggregation::cumulative_temporality_selector(),
+    ))
+    .with_exporter(exporter)
+    .with_collect_period(Duration::from_secs(3600))
+    .build();
+    let cx = Context::new();
+    controller.start(&cx, runtime::Tokio).unwrap();
+
+    let counter = controller
+        .versioned_meter("test", None, None)
+        .u64_counter("requests")
+        .with_description("Handled requests")
+        .init();
+    counter.add(&cx, 3, attributes);
+    tokio::task::spawn_blocking(move || controller.stop(&cx))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        requests = server.received_requests().await.unwrap();
+        if !requests.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    requests.pop().expect("expected a metrics request")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn collected_metrics_are_sent_to_the_metrics_stream() {
+    let server = support::mock_parseable().await;
+    let request = export_counter(
+        &server,
+        support::builder_for(&server),
+        &[KeyValue::new("route", "/orders")],
+    )
+    .await;
+
+    assert_eq!(
+        support::header(&request, "X-P-Stream").as_deref(),
+        Some("test-service-metrics")
+    );
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["metric_name"], "requests");
+    assert_eq!(records[0]["description"], "Handled requests");
+    assert_eq!(records[0]["aggregation"], "sum");
+    assert_eq!(records[0]["value"], 3.0);
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["route=/orders"])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn metric_attributes_are_redacted() {
+    let server = support::mock_parseable().await;
+    let builder = support::builder_for(&server).with_redacted_keys(vec!["user.email".to_string()]);
+    let request = export_counter(
+        &server,
+        builder,
+        &[
+            KeyValue::new("route", "/orders"),
+            KeyValue::new("user.email", "alice@example.com"),
+        ],
+    )
+    .await;
+
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+    let attributes = records[0]["attributes"].as_array().unwrap();
+    assert!(
+        attributes.contains(&"user.email=***".into()),
+        "{attributes:?}"
+    );
+    assert!(
+        attributes.contains(&"route=/orders".into()),
+        "{attributes:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn metric_timestamps_have_the_configured_precision() {
+    let server = support::mock_parseable().await;
+    let builder = support::builder_for(&server).with_timestamp_precision(TimestampPrecision::Nanos);
+    let request = export_counter(&server, builder, &[]).await;
+
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+    for field in ["start_time", "end_time"] {
+        let timestamp = records[0][field].as_str().unwrap();
+        let (_, fraction) = timestamp.rsplit_once('.').unwrap();
+        assert_eq!(fraction.len(), "123456789Z".len(), "{timestamp}");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resource_headers_are_left_out_of_metric_records() {
+    let server = support::mock_parseable().await;
+    // The controller's resource defaults to the SDK's default resource as well.
+    let builder = support::builder_for(&server).with_resource_as_headers();
+    let request = export_counter(&server, builder, &[]).await;
+
+    assert!(support::header(&request, "X-P-Meta-service.name").is_some());
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+    let resource_attributes = records[0]["resource_attributes"].as_array().unwrap();
+    assert!(
+        !resource_attributes
+            .iter()
+            .any(|attribute| attribute.as_str().unwrap().starts_with("service.name=")),
+        "{resource_attributes:?}"
+    );
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use opentelemetry::runtime;
+use opentelemetry::sdk::trace;
+use opentelemetry::trace::Tracer;
+use opentelemetry_parseable::BatchSettings;
+use wiremock::MockServer;
+
+mod support;
+
+fn tracer_for(server: &MockServer, min_spans: usize, max_age: Duration) -> trace::Tracer {
+    support::builder_for(server)
+        .with_batch_settings(BatchSettings {
+            max_queue_size: 1024,
+            max_export_batch_size: 1024,
+            scheduled_delay: Duration::from_millis(50),
+        })
+        .with_min_batch_size(min_spans, max_age)
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap()
+}
+
+async fn wait_for_requests(server: &MockServer, count: usize) {
+    for _ in 0..100 {
+        if server.received_requests().await.unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {count} requests");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_are_held_until_the_minimum_is_buffered() {
+    let server = support::mock_parseable().await;
+    let tracer = tracer_for(&server, 3, Duration::from_secs(3600));
+
+    tracer.in_span("first", |_| {});
+    tracer.in_span("second", |_| {});
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    tracer.in_span("third", |_| {});
+    wait_for_requests(&server, 1).await;
+    assert_eq!(support::received_records(&server).await.len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_older_than_the_max_age_are_exported() {
+    let server = support::mock_parseable().await;
+    let tracer = tracer_for(&server, 100, Duration::from_millis(300));
+
+    tracer.in_span("lonely", |_| {});
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    wait_for_requests(&server, 1).await;
+    let records = support::received_records(&server).await;
+    assert_eq!(records[0]["span_name"], "lonely");
+}
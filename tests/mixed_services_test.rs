@@ -0,0 +1,42 @@
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::global;
+use opentelemetry::sdk::export::trace::{SpanData, SpanExporter};
+use opentelemetry::sdk::Resource;
+use opentelemetry::KeyValue;
+
+mod support;
+
+fn span_of(service: &'static str) -> SpanData {
+    let mut span = support::span_data("job");
+    span.resource = Cow::Owned(Resource::new([KeyValue::new("service.name", service)]));
+    span
+}
+
+// The global error handler is process wide, so this test has its own binary.
+#[tokio::test]
+async fn mixed_batches_are_reported_once_per_set_of_services() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let handler_reports = reports.clone();
+    global::set_error_handler(move |error| {
+        handler_reports.lock().unwrap().push(error.to_string());
+    })
+    .unwrap();
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    for batch in [
+        vec![span_of("api"), span_of("worker")],
+        vec![span_of("worker"), span_of("api"), span_of("api")],
+        vec![span_of("api"), span_of("billing")],
+        vec![span_of("api"), span_of("api")],
+    ] {
+        exporter.export(batch).await.unwrap();
+    }
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 2, "{reports:?}");
+    assert!(reports[0].contains(r#"{"api", "worker"}"#), "{reports:?}");
+    assert!(reports[1].contains(r#"{"api", "billing"}"#), "{reports:?}");
+}
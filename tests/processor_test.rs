@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use opentelemetry::runtime::Tokio;
+use opentelemetry::sdk::trace::SpanProcessor;
+use opentelemetry::KeyValue;
+use opentelemetry_parseable::{BatchSettings, ByteBoundedProcessor};
+use wiremock::MockServer;
+
+mod support;
+
+const MAX_BYTES: usize = 4096;
+
+fn processor_for(server: &MockServer) -> ByteBoundedProcessor {
+    let exporter = support::builder_for(server).build_exporter().unwrap();
+    let settings = BatchSettings {
+        max_queue_size: 1024,
+        max_export_batch_size: 1024,
+        scheduled_delay: Duration::from_secs(3600),
+    };
+    ByteBoundedProcessor::new(exporter, Tokio, settings, MAX_BYTES)
+}
+
+async fn wait_for_requests(server: &MockServer, count: usize) {
+    for _ in 0..100 {
+        if server.received_requests().await.unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {count} requests");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn small_spans_wait_for_another_trigger() {
+    let server = support::mock_parseable().await;
+    let mut processor = processor_for(&server);
+
+    for _ in 0..5 {
+        processor.on_end(support::span_data("small"));
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    processor.shutdown().unwrap();
+    assert_eq!(support::received_records(&server).await.len(), 5);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn crossing_the_byte_threshold_exports_the_buffer() {
+    let server = support::mock_parseable().await;
+    let mut processor = processor_for(&server);
+
+    processor.on_end(support::span_data("small"));
+    let mut large = support::span_data("large");
+    large
+        .attributes
+        .insert(KeyValue::new("payload", "x".repeat(MAX_BYTES)));
+    processor.on_end(large);
+    processor.on_end(support::span_data("after"));
+
+    wait_for_requests(&server, 1).await;
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["span_name"], "large");
+
+    processor.shutdown().unwrap();
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[2]["span_name"], "after");
+}
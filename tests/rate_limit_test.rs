@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::{FixedClock, JitterStrategy};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+async fn rate_limited_once(retry_after: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", retry_after))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn rate_limited_request_is_retried_after_the_requested_delay() {
+    let server = rate_limited_once("1").await;
+    let mut exporter = support::builder_for(&server).build_exporter().unwrap();
+
+    let started = Instant::now();
+    exporter
+        .export(vec![support::span_data("limited")])
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["span_name"], "limited");
+}
+
+#[tokio::test]
+async fn delays_beyond_the_budget_are_not_waited_for() {
+    let server = rate_limited_once("60").await;
+    let exporter = support::builder_for(&server)
+        .with_rate_limit_retries(3, Duration::from_secs(5))
+        .build_exporter()
+        .unwrap();
+
+    let started = Instant::now();
+    let error = exporter
+        .export_now(vec![support::span_data("limited")])
+        .await
+        .unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(error.status.map(|s| s.as_u16()), Some(429));
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn retry_after_dates_are_measured_against_the_configured_clock() {
+    let server = rate_limited_once("Wed, 21 Oct 2015 07:28:01 GMT").await;
+    // One second before the date Parseable asks to retry at.
+    let now = UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+    let mut exporter = support::builder_for(&server)
+        .with_clock(FixedClock(now))
+        .build_exporter()
+        .unwrap();
+
+    let started = Instant::now();
+    exporter
+        .export(vec![support::span_data("limited")])
+        .await
+        .unwrap();
+
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_secs(1) && waited < Duration::from_secs(3));
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn rate_limited_request_without_retry_after_is_retried_after_the_backoff() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_retry_jitter(JitterStrategy::None)
+        .build_exporter()
+        .unwrap();
+
+    let started = Instant::now();
+    exporter
+        .export(vec![support::span_data("limited")])
+        .await
+        .unwrap();
+
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_millis(500) && waited < Duration::from_secs(3));
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::sdk::resource::ResourceDetector;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::trace::{Span as _, Tracer};
+use opentelemetry::{runtime, KeyValue};
+use opentelemetry_parseable::{CachedDetector, EnvVarDetector};
+use tracing_subscriber::prelude::*;
+
+mod support;
+
+static DETECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+struct SlowDetector;
+
+impl ResourceDetector for SlowDetector {
+    fn detect(&self, _timeout: Duration) -> Resource {
+        DETECTIONS.fetch_add(1, Ordering::SeqCst);
+        Resource::new([KeyValue::new("cloud.region", "eu-west-1")])
+    }
+}
+
+#[test]
+fn detector_runs_once_for_all_clones() {
+    let timeout = Duration::from_secs(1);
+    let detector = CachedDetector::new(SlowDetector);
+    let first = Resource::from_detectors(timeout, vec![Box::new(detector.clone())]);
+    let second = Resource::from_detectors(timeout, vec![Box::new(detector)]);
+
+    assert_eq!(DETECTIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(first, second);
+    assert_eq!(
+        second
+            .get("cloud.region".into())
+            .map(|v| v.as_str().into_owned()),
+        Some("eu-west-1".to_string())
+    );
+}
+
+#[test]
+fn detector_runs_once_across_exporter_rebuilds() {
+    let timeout = Duration::from_secs(1);
+    let rebuild =
+        || Resource::from_detectors(timeout, vec![Box::new(CachedDetector::new(SlowDetector))]);
+    let first = rebuild();
+    let second = rebuild();
+
+    assert_eq!(DETECTIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn differently_configured_detectors_are_cached_separately() {
+    let timeout = Duration::from_secs(1);
+    let region = |detector: EnvVarDetector| {
+        CachedDetector::new(detector)
+            .detect(timeout)
+            .get("region".into())
+            .map(|v| v.as_str().into_owned())
+    };
+    std::env::set_var("RESOURCE_TEST_REGION_EU", "eu-west-1");
+    std::env::set_var("RESOURCE_TEST_REGION_US", "us-east-1");
+
+    assert_eq!(
+        region(EnvVarDetector::new().with_var("region", "RESOURCE_TEST_REGION_EU")),
+        Some("eu-west-1".to_string())
+    );
+    assert_eq!(
+        region(EnvVarDetector::new().with_var("region", "RESOURCE_TEST_REGION_US")),
+        Some("us-east-1".to_string())
+    );
+}
+
+#[test]
+fn env_var_detector_reads_the_mapped_variables() {
+    std::env::set_var("RESOURCE_TEST_PIPELINE_ID", "4711");
+    std::env::set_var("BUILD_DATE_TIME", "2023-11-14");
+    std::env::remove_var("Q_VHOST");
+    let detector = EnvVarDetector::new()
+        .with_var("build_number", "RESOURCE_TEST_PIPELINE_ID")
+        .without("build_date_time");
+
+    let resource = detector.detect(Duration::from_secs(1));
+
+    let value = |key: &'static str| resource.get(key.into()).map(|v| v.as_str().into_owned());
+    assert_eq!(value("build_number"), Some("4711".to_string()));
+    assert_eq!(value("build_date_time"), None);
+    assert_eq!(value("vhost"), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn added_resource_attributes_are_merged_into_the_resource() {
+    let server = support::mock_parseable().await;
+    let config = trace::config().with_resource(Resource::new([
+        KeyValue::new("service.name", "api"),
+        KeyValue::new("deployment.environment", "dev"),
+    ]));
+    let tracer = support::builder_for(&server)
+        .with_resource_attributes([KeyValue::new("deployment.environment", "prod")])
+        .install_batch(runtime::Tokio, config)
+        .unwrap();
+
+    tracer.start("tagged").end();
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    let resource = records[0]["resource_attributes"].as_array().unwrap();
+    assert!(
+        resource.contains(&"service.name=api".into()),
+        "{resource:?}"
+    );
+    assert!(
+        resource.contains(&"deployment.environment=prod".into()),
+        "{resource:?}"
+    );
+    assert!(
+        !resource.contains(&"deployment.environment=dev".into()),
+        "{resource:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resource_headers_come_from_the_installed_resource() {
+    let server = support::mock_parseable().await;
+    let config = trace::config().with_resource(Resource::new([
+        KeyValue::new("service.name", "api"),
+        KeyValue::new("Build Id", "42"),
+    ]));
+    let tracer = support::builder_for(&server)
+        .with_resource_as_headers()
+        .install_batch(runtime::Tokio, config)
+        .unwrap();
+
+    tracer.start("span").end();
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        support::header(&requests[0], "X-P-Meta-service.name").as_deref(),
+        Some("api")
+    );
+    let records = support::received_records(&server).await;
+    assert_eq!(
+        records[0]["resource_attributes"],
+        serde_json::json!(["Build Id=42"])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resource_headers_come_from_the_given_resource_on_every_build_path() {
+    let server = support::mock_parseable().await;
+    let resource = Resource::new([KeyValue::new("service.name", "api")]);
+    let builder = || {
+        support::builder_for(&server)
+            .with_resource_as_headers()
+            .with_resource(resource.clone())
+    };
+    let mut exporter = builder().build_exporter().unwrap();
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+    let layer = builder().build_log_layer().unwrap();
+    {
+        let _default =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(layer.clone()));
+        tracing::info!("signed in");
+    }
+    layer.flush().await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        assert_eq!(
+            support::header(request, "X-P-Meta-service.name").as_deref(),
+            Some("api")
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry_parseable::JitterStrategy;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+async fn failing_once(status: u16) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(status))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    let server = failing_once(503).await;
+    let mut exporter = support::builder_for(&server)
+        .with_retry(3, Duration::from_millis(10))
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("unavailable")])
+        .await
+        .unwrap();
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn persistent_server_errors_fail_after_the_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let exporter = support::builder_for(&server)
+        .with_retry(2, Duration::from_millis(10))
+        .with_retry_jitter(JitterStrategy::None)
+        .build_exporter()
+        .unwrap();
+
+    let error = exporter
+        .export_now(vec![support::span_data("unavailable")])
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status.map(|s| s.as_u16()), Some(500));
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let server = failing_once(400).await;
+    let exporter = support::builder_for(&server)
+        .with_retry(3, Duration::from_millis(10))
+        .build_exporter()
+        .unwrap();
+
+    let error = exporter
+        .export_now(vec![support::span_data("malformed")])
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status.map(|s| s.as_u16()), Some(400));
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::trace::{
+    Event, Link, SpanContext, SpanId, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::KeyValue;
+use opentelemetry_parseable::{NullHandling, ParseableExporterBuilder};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+#[tokio::test]
+async fn schema_is_published_once_before_first_export() {
+    let server = support::mock_parseable().await;
+    Mock::given(method("PUT"))
+        .and(path("/api/v1/logstream/test-service"))
+        .and(header("X-P-Static-Schema-Flag", "true"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_exporter_id()
+        .with_publish_schema()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("first")])
+        .await
+        .unwrap();
+    exporter
+        .export(vec![support::span_data("second")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests[0].method.as_ref(), "PUT");
+    let schema: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let fields = schema["fields"].as_array().unwrap();
+    assert!(fields.contains(&serde_json::json!({"name": "span_name", "data_type": "string"})));
+    assert!(fields.contains(&serde_json::json!({"name": "duration_ms", "data_type": "int"})));
+    assert!(fields.contains(&serde_json::json!({"name": "exporter_id", "data_type": "string"})));
+    assert_eq!(support::received_records(&server).await.len(), 3);
+}
+
+#[tokio::test]
+async fn schema_goes_to_the_stream_api_next_to_a_configured_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/ingest/"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    let endpoint = format!("{}/api/v1/ingest/", server.uri()).parse().unwrap();
+    let mut exporter = ParseableExporterBuilder::default()
+        .with_endpoint(endpoint)
+        .with_service_name("orders api")
+        .with_publish_schema()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests[0].method.as_ref(), "PUT");
+    assert_eq!(requests[0].url.path(), "/api/v1/logstream/orders%20api");
+}
+
+#[tokio::test]
+async fn export_continues_when_schema_is_rejected() {
+    let server = support::mock_parseable().await;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_publish_schema()
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("span")])
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].url.path(), support::INGEST_PATH);
+}
+
+#[tokio::test]
+async fn schema_covers_every_field_of_the_exported_records() {
+    let server = support::mock_parseable().await;
+    Mock::given(method("PUT"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_publish_schema()
+        .with_exporter_id()
+        .with_stream_field()
+        .with_local_root()
+        .with_correlation_id_from_baggage("correlation_id")
+        .with_null_handling(NullHandling::EmitNull)
+        .build_exporter()
+        .unwrap();
+
+    let mut span = support::span_data("span");
+    span.status = Status::error("failed");
+    span.attributes
+        .insert(KeyValue::new("parseable.correlation_id", "order-42"));
+    span.links.extend([Link::new(
+        SpanContext::new(
+            TraceId::from_bytes(7u128.to_be_bytes()),
+            SpanId::from_bytes(8u64.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        ),
+        vec![],
+    )]);
+    let mut with_event = span.clone();
+    with_event
+        .events
+        .append_vec(&mut vec![Event::new("event", SystemTime::now(), vec![], 0)]);
+    exporter.export(vec![span, with_event]).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let schema: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let schema_fields: BTreeSet<&str> = schema["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["name"].as_str().unwrap())
+        .collect();
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&requests[1].body).unwrap();
+    let record_fields: BTreeSet<&str> = records
+        .iter()
+        .flat_map(|record| record.as_object().unwrap().keys())
+        .map(String::as_str)
+        .collect();
+    assert_eq!(record_fields, schema_fields);
+}
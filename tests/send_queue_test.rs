@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+async fn wait_for_requests(server: &MockServer, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.received_requests().await.unwrap().len() < count {
+        assert!(Instant::now() < deadline, "requests were not sent in time");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn queued_exports_complete_before_their_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .mount(&server)
+        .await;
+    let mut exporter = support::builder_for(&server)
+        .with_send_queue(4)
+        .build_exporter()
+        .unwrap();
+
+    let started = Instant::now();
+    for name in ["first", "second", "third"] {
+        exporter
+            .export(vec![support::span_data(name)])
+            .await
+            .unwrap();
+    }
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    wait_for_requests(&server, 3).await;
+    let names: Vec<String> = support::received_records(&server)
+        .await
+        .iter()
+        .map(|r| r["span_name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["first", "second", "third"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn queued_failures_are_passed_to_the_error_handler() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(307).insert_header("Location", "/elsewhere"))
+        .mount(&server)
+        .await;
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let recorded = statuses.clone();
+    let mut exporter = support::builder_for(&server)
+        .with_send_queue(4)
+        .with_error_handler(move |error| recorded.lock().unwrap().push(error.status))
+        .build_exporter()
+        .unwrap();
+
+    exporter
+        .export(vec![support::span_data("redirected")])
+        .await
+        .unwrap();
+    tokio::task::block_in_place(|| exporter.shutdown());
+
+    let statuses = statuses.lock().unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].map(|s| s.as_u16()), Some(307));
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::trace::{Tracer, TracerProvider as _};
+use opentelemetry::{runtime, KeyValue};
+use opentelemetry_parseable::MixedServices;
+
+mod support;
+
+fn provider_for(
+    service: &'static str,
+    exporter: opentelemetry_parseable::ParseableExporter,
+) -> trace::TracerProvider {
+    trace::TracerProvider::builder()
+        .with_config(
+            trace::config().with_resource(Resource::new([KeyValue::new("service.name", service)])),
+        )
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .build()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn providers_sharing_an_exporter_export_to_their_services_streams() {
+    let server = support::mock_parseable().await;
+    let exporter = support::builder_for(&server)
+        .with_mixed_services(MixedServices::Split)
+        .build_exporter()
+        .unwrap();
+    let checkout = provider_for("checkout", exporter.clone());
+    let billing = provider_for("billing", exporter);
+
+    checkout.tracer("test").in_span("reserve", |_| {});
+    billing.tracer("test").in_span("charge", |_| {});
+    tokio::task::spawn_blocking(move || {
+        checkout.force_flush();
+        billing.force_flush();
+    })
+    .await
+    .unwrap();
+
+    let mut streams: HashMap<String, Vec<String>> = HashMap::new();
+    for request in server.received_requests().await.unwrap() {
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+        streams
+            .entry(support::header(&request, "X-P-Stream").unwrap())
+            .or_default()
+            .extend(
+                records
+                    .iter()
+                    .map(|record| record["span_name"].as_str().unwrap().to_string()),
+            );
+    }
+    assert_eq!(streams["checkout"], ["reserve"]);
+    assert_eq!(streams["billing"], ["charge"]);
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod support;
+
+async fn slow_parseable(delay: Duration) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(support::INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200).set_delay(delay))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shutdown_waits_for_in_flight_exports() {
+    let server = slow_parseable(Duration::from_millis(500)).await;
+    let mut exporter = support::builder_for(&server)
+        .with_shutdown_grace_period(Duration::from_secs(10))
+        .build_exporter()
+        .unwrap();
+
+    let export = tokio::spawn(exporter.export(vec![support::span_data("slow")]));
+    let started = Instant::now();
+    tokio::task::block_in_place(|| exporter.shutdown());
+
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_millis(400) && waited < Duration::from_secs(10));
+    export.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shutdown_gives_up_after_the_grace_period() {
+    let server = slow_parseable(Duration::from_secs(10)).await;
+    let mut exporter = support::builder_for(&server)
+        .with_shutdown_grace_period(Duration::from_millis(200))
+        .build_exporter()
+        .unwrap();
+
+    let export = tokio::spawn(exporter.export(vec![support::span_data("stuck")]));
+    let started = Instant::now();
+    tokio::task::block_in_place(|| exporter.shutdown());
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(!export.is_finished());
+    export.abort();
+}
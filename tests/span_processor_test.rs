@@ -0,0 +1,48 @@
+use opentelemetry::sdk::export::trace::SpanData;
+use opentelemetry::sdk::trace::{self, Span, SpanProcessor};
+use opentelemetry::trace::{Span as _, TraceResult, Tracer};
+use opentelemetry::{runtime, Context, KeyValue};
+
+mod support;
+
+#[derive(Debug)]
+struct Enricher;
+
+impl SpanProcessor for Enricher {
+    fn on_start(&self, span: &mut Span, _cx: &Context) {
+        span.set_attribute(KeyValue::new("deployment", "canary"));
+    }
+
+    fn on_end(&self, _span: SpanData) {}
+
+    fn force_flush(&self) -> TraceResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn added_processors_run_before_the_exporting_one() {
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_span_processor(Enricher)
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+
+    tracer.start("enriched").end();
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "enriched");
+    assert_eq!(
+        records[0]["attributes"],
+        serde_json::json!(["deployment=canary"])
+    );
+}
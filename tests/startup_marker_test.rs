@@ -0,0 +1,33 @@
+use opentelemetry::{runtime, sdk::trace};
+
+mod support;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn startup_marker_is_sent_on_install() {
+    let server = support::mock_parseable().await;
+    let tracer = support::builder_for(&server)
+        .with_startup_marker()
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+
+    let provider = tracer.provider().unwrap();
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let records = support::received_records(&server).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["span_name"], "parseable exporter started");
+    let attributes: Vec<&str> = records[0]["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    assert!(attributes.contains(&concat!("exporter.version=", env!("CARGO_PKG_VERSION"))));
+    assert!(attributes.contains(&"exporter.sampler=ParentBased(AlwaysOn)"));
+    assert!(attributes.contains(&"exporter.batch_size=8192"));
+    assert!(attributes
+        .iter()
+        .any(|a| a.starts_with("exporter.config_fingerprint=")));
+}
@@ -0,0 +1,101 @@
+#![allow(dead_code)]
+
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::sdk::{
+    export::trace::SpanData,
+    trace::{EvictedHashMap, EvictedQueue},
+    InstrumentationLibrary, Resource,
+};
+use opentelemetry::trace::{
+    SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::KeyValue;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use opentelemetry_parseable::ParseableExporterBuilder;
+
+pub const INGEST_PATH: &str = "/api/v1/ingest";
+
+/// Start a mock Parseable server that accepts every ingest request.
+pub async fn mock_parseable() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(INGEST_PATH))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Exporter builder targeting the given mock server.
+pub fn builder_for(server: &MockServer) -> ParseableExporterBuilder {
+    let address = server.address();
+    ParseableExporterBuilder::default()
+        .with_host(address.ip().to_string())
+        .with_port(address.port().to_string())
+        .with_service_name("test-service")
+}
+
+/// A finished, sampled span with the given name.
+pub fn span_data(name: &'static str) -> SpanData {
+    span_data_with_ids(name, 1, 1, SpanId::INVALID)
+}
+
+pub fn span_data_with_ids(
+    name: &'static str,
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: SpanId,
+) -> SpanData {
+    let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    SpanData {
+        span_context: SpanContext::new(
+            TraceId::from_bytes(trace_id.to_be_bytes()),
+            SpanId::from_bytes(span_id.to_be_bytes()),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        ),
+        parent_span_id,
+        span_kind: SpanKind::Internal,
+        name: Cow::Borrowed(name),
+        start_time,
+        end_time: start_time + Duration::from_millis(250),
+        attributes: EvictedHashMap::new(128, 0),
+        events: EvictedQueue::new(128),
+        links: EvictedQueue::new(128),
+        status: Status::Unset,
+        resource: Cow::Owned(Resource::new([KeyValue::new(
+            "service.name",
+            "test-service",
+        )])),
+        instrumentation_lib: InstrumentationLibrary::new("test", None, None),
+    }
+}
+
+/// Bodies of all ingest requests the mock server received, decoded as JSON.
+pub async fn received_records(server: &MockServer) -> Vec<serde_json::Value> {
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|request| match serde_json::from_slice(&request.body) {
+            Ok(serde_json::Value::Array(records)) => records,
+            Ok(record) => vec![record],
+            Err(e) => panic!("request body is not JSON: {e}"),
+        })
+        .collect()
+}
+
+/// Value of a request header, matched case-insensitively.
+pub fn header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key.as_str().eq_ignore_ascii_case(name))
+        .map(|(_, values)| values.last().as_str().to_string())
+}
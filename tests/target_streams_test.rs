@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use opentelemetry::sdk::export::trace::SpanExporter;
+use opentelemetry::{runtime, sdk::trace, Key, KeyValue};
+use tracing_subscriber::prelude::*;
+
+mod support;
+
+mod db {
+    pub mod pool {
+        pub fn checkout() {
+            tracing::info_span!("checkout").in_scope(|| {});
+        }
+    }
+
+    pub fn query() {
+        tracing::info_span!("query").in_scope(|| {});
+    }
+}
+
+fn handle_request() {
+    tracing::info_span!("request").in_scope(|| {});
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_are_routed_to_the_stream_of_their_target() {
+    let server = support::mock_parseable().await;
+    let routes = HashMap::from([(
+        concat!(module_path!(), "::db").to_string(),
+        "db-traces".to_string(),
+    )]);
+    let tracer = support::builder_for(&server)
+        .with_target_streams(routes)
+        .install_batch(runtime::Tokio, trace::config())
+        .unwrap();
+    let provider = tracer.provider().unwrap();
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+    tracing::subscriber::with_default(subscriber, || {
+        db::query();
+        handle_request();
+        db::pool::checkout();
+    });
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap();
+
+    let streams = received_streams(&server).await;
+    assert_eq!(streams["db-traces"], ["query", "checkout"]);
+    assert_eq!(streams["test-service"], ["request"]);
+}
+
+/// Names of the spans received by the mock server, by the stream they were sent to.
+async fn received_streams(server: &wiremock::MockServer) -> HashMap<String, Vec<String>> {
+    let mut streams: HashMap<String, Vec<String>> = HashMap::new();
+    for request in server.received_requests().await.unwrap() {
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&request.body).unwrap();
+        streams
+            .entry(support::header(&request, "X-P-Stream").unwrap())
+            .or_default()
+            .extend(
+                records
+                    .iter()
+                    .map(|record| record["span_name"].as_str().unwrap().to_string()),
+            );
+    }
+    streams
+}
+
+#[tokio::test]
+async fn the_stream_router_overrides_the_stream_of_a_span() {
+    let server = support::mock_parseable().await;
+    let mut exporter = support::builder_for(&server)
+        .with_stream_router(|span| {
+            span.attributes
+                .get(&Key::from_static_str("stream.override"))
+                .map(|stream| stream.as_str().into_owned())
+        })
+        .build_exporter()
+        .unwrap();
+
+    let mut invoice = support::span_data("invoice");
+    invoice
+        .attributes
+        .insert(KeyValue::new("stream.override", "billing"));
+    exporter
+        .export(vec![invoice, support::span_data("login")])
+        .await
+        .unwrap();
+
+    let streams = received_streams(&server).await;
+    assert_eq!(streams["billing"], ["invoice"]);
+    assert_eq!(streams["test-service"], ["login"]);
+}